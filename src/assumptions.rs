@@ -1,3 +1,7 @@
 mod assumption;
 pub mod assumption_scenario;
+pub mod commission;
 mod helpers;
+pub mod premium_rules;
+pub mod reinsurance;
+pub mod tax;
@@ -2,10 +2,15 @@ use crate::assumptions::assumption::{
     get_acq_exp_df, get_inf_rate_df, get_lapse_rate_df, get_load_rate_df, get_mort_rate_df,
     get_mtn_exp_df, get_spot_rate_df,
 };
+use crate::assumptions::commission::{Clawback, CommissionRate, CommissionScale};
 use crate::assumptions::helpers::{
     get_indices_names_hashmap, get_sheet_by_name, parse_col_by_index_to_string,
 };
+use crate::assumptions::premium_rules::{PremiumRules, RoundingBasis};
+use crate::assumptions::reinsurance::ReinsuranceTreaty;
+use crate::assumptions::tax::TaxBasis;
 use polars::prelude::*;
+use serde_json::{Value, json};
 use std::collections::VecDeque;
 //---------------------------------------------------------------------------------------------------------
 // STRUCTS
@@ -20,8 +25,109 @@ pub struct AssumptionScenario {
     pub mtn: DataFrame,
     pub spot: DataFrame,
     pub load: DataFrame,
+    pub reserve_floor: bool, // Floor policy reserves at zero
+    pub premium_target: Option<PremiumTarget>, // Solve the premium to a target instead of loading the net premium
+    pub commission: CommissionScale,
+    pub modal_loadings: Vec<(i32, f64)>, // Loading on the net premium by payment frequency
+    pub decrement_conversion: DecrementConversion,
+    pub decrement_order: DecrementOrder,
+    pub premium_rules: PremiumRules,
+    pub tax: TaxBasis,
+    pub reinsurance: ReinsuranceTreaty,
+    pub earned_rate: Option<f64>, // Annual rate earned on assets - the spot rate if None
+    pub mass_lapse: f64,          // Share of policies lapsing at the start of the projection
+    pub pricing_basis: Option<Box<AssumptionScenario>>, // Premiums set on this basis when given
 }
 
+// Profit criterion the premium is solved to, allowing for all cashflows
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum PremiumTarget {
+    ZeroProfit {
+        risk_discount_rate: f64,
+    },
+    ProfitMargin {
+        margin: f64,
+        risk_discount_rate: f64,
+    }, // PV profit as a share of PV premiums
+    Irr {
+        hurdle_rate: f64,
+    },
+}
+
+impl PremiumTarget {
+    // Every target is a nil PV of profit less margin at some rate
+    pub fn rate_and_margin(&self) -> (f64, f64) {
+        match *self {
+            PremiumTarget::ZeroProfit { risk_discount_rate } => (risk_discount_rate, 0.0),
+            PremiumTarget::ProfitMargin {
+                margin,
+                risk_discount_rate,
+            } => (risk_discount_rate, margin),
+            PremiumTarget::Irr { hurdle_rate } => (hurdle_rate, 0.0),
+        }
+    }
+}
+
+// Conversion of annual decrement rates to monthly rates
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DecrementConversion {
+    #[default]
+    ConstantForce, // 1 - (1 - q)^(1/12)
+    Udd,      // Uniform distribution of decrements over the policy year
+    Divide12, // q / 12
+}
+
+impl DecrementConversion {
+    // Monthly rate in the given month (0 to 11) of the policy year
+    pub fn monthly_rate(&self, q: f64, month_in_year: i32) -> f64 {
+        match self {
+            DecrementConversion::ConstantForce => 1.0 - (1.0 - q).powf(1.0 / 12.0),
+            DecrementConversion::Udd => {
+                let survived = 1.0 - q * month_in_year as f64 / 12.0;
+                if survived > 0.0 {
+                    q / 12.0 / survived
+                } else {
+                    1.0
+                }
+            }
+            DecrementConversion::Divide12 => q / 12.0,
+        }
+    }
+}
+
+// Order in which death and lapse apply within a month
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum DecrementOrder {
+    #[default]
+    DeathFirst,
+    LapseFirst,
+    MidMonth, // Each decrement is exposed to half of the other
+}
+
+impl DecrementOrder {
+    // Deaths and lapses out of the given policies exposed
+    pub fn apply(&self, pols: f64, mort_rate: f64, lapse_rate: f64) -> (f64, f64) {
+        match self {
+            DecrementOrder::DeathFirst => {
+                let death = pols * mort_rate;
+                (death, (pols - death) * lapse_rate)
+            }
+            DecrementOrder::LapseFirst => {
+                let lapse = pols * lapse_rate;
+                ((pols - lapse) * mort_rate, lapse)
+            }
+            DecrementOrder::MidMonth => (
+                pols * mort_rate * (1.0 - 0.5 * lapse_rate),
+                pols * lapse_rate * (1.0 - 0.5 * mort_rate),
+            ),
+        }
+    }
+}
+
+#[allow(dead_code)]
 impl AssumptionScenario {
     pub fn new_by_name(scenario_name: &str) -> PolarsResult<Self> {
         // Get the assumption scenario by name
@@ -65,10 +171,284 @@ impl AssumptionScenario {
                 .ok_or_else(|| PolarsError::ComputeError("Missing 'spot' assumption".into()))?,
             load: load
                 .ok_or_else(|| PolarsError::ComputeError("Missing 'load' assumption".into()))?,
+            reserve_floor: false,
+            premium_target: None,
+            commission: CommissionScale::default(),
+            modal_loadings: Vec::new(),
+            decrement_conversion: DecrementConversion::default(),
+            decrement_order: DecrementOrder::default(),
+            premium_rules: PremiumRules::default(),
+            tax: TaxBasis::default(),
+            reinsurance: ReinsuranceTreaty::default(),
+            earned_rate: None,
+            mass_lapse: 0.0,
+            pricing_basis: None,
         };
 
         Ok(result)
     }
+
+    pub fn with_earned_rate(mut self, earned_rate: f64) -> Self {
+        self.earned_rate = Some(earned_rate);
+        self
+    }
+
+    pub fn with_mass_lapse(mut self, mass_lapse: f64) -> Self {
+        self.mass_lapse = mass_lapse;
+        self
+    }
+
+    pub fn with_pricing_basis(mut self, pricing_basis: AssumptionScenario) -> Self {
+        self.pricing_basis = Some(Box::new(pricing_basis));
+        self
+    }
+
+    pub fn with_reinsurance(mut self, reinsurance: ReinsuranceTreaty) -> Self {
+        self.reinsurance = reinsurance;
+        self
+    }
+
+    pub fn with_tax(mut self, tax: TaxBasis) -> Self {
+        self.tax = tax;
+        self
+    }
+
+    pub fn with_premium_rules(mut self, premium_rules: PremiumRules) -> Self {
+        self.premium_rules = premium_rules;
+        self
+    }
+
+    pub fn with_decrement_conversion(mut self, decrement_conversion: DecrementConversion) -> Self {
+        self.decrement_conversion = decrement_conversion;
+        self
+    }
+
+    pub fn with_decrement_order(mut self, decrement_order: DecrementOrder) -> Self {
+        self.decrement_order = decrement_order;
+        self
+    }
+
+    pub fn with_modal_loading(mut self, payment_freq: i32, loading: f64) -> Self {
+        self.modal_loadings
+            .retain(|(freq, _)| *freq != payment_freq);
+        self.modal_loadings.push((payment_freq, loading));
+        self
+    }
+
+    // No loading for payment frequencies without one
+    pub fn modal_loading(&self, payment_freq: i32) -> f64 {
+        self.modal_loadings
+            .iter()
+            .find(|(freq, _)| *freq == payment_freq)
+            .map(|(_, loading)| *loading)
+            .unwrap_or(0.0)
+    }
+
+    pub fn with_commission(mut self, commission: CommissionScale) -> Self {
+        self.commission = commission;
+        self
+    }
+
+    pub fn with_premium_target(mut self, premium_target: PremiumTarget) -> Self {
+        self.premium_target = Some(premium_target);
+        self
+    }
+
+    pub fn with_reserve_floor(mut self, reserve_floor: bool) -> Self {
+        self.reserve_floor = reserve_floor;
+        self
+    }
+
+    // Name of the scenario and every setting made on top of its assumption tables, so a run setup can
+    // be saved with its results - the tables themselves are reloaded by name
+    pub fn to_json(&self) -> Value {
+        let premium_target = self.premium_target.as_ref().map(|target| match *target {
+            PremiumTarget::ZeroProfit { risk_discount_rate } => json!({
+                "type": "zero_profit",
+                "risk_discount_rate": risk_discount_rate,
+            }),
+            PremiumTarget::ProfitMargin {
+                margin,
+                risk_discount_rate,
+            } => json!({
+                "type": "profit_margin",
+                "margin": margin,
+                "risk_discount_rate": risk_discount_rate,
+            }),
+            PremiumTarget::Irr { hurdle_rate } => json!({
+                "type": "irr",
+                "hurdle_rate": hurdle_rate,
+            }),
+        });
+
+        let commission_rates: Vec<Value> = self
+            .commission
+            .rates
+            .iter()
+            .map(|r| json!({"year": r.year, "rate": r.rate, "term": r.term, "channel": r.channel}))
+            .collect();
+        let clawback = self
+            .commission
+            .clawback
+            .as_ref()
+            .map(|c| json!({"period_mth": c.period_mth, "rate": c.rate}));
+
+        let decrement_conversion = match self.decrement_conversion {
+            DecrementConversion::ConstantForce => "constant_force",
+            DecrementConversion::Udd => "udd",
+            DecrementConversion::Divide12 => "divide12",
+        };
+        let decrement_order = match self.decrement_order {
+            DecrementOrder::DeathFirst => "death_first",
+            DecrementOrder::LapseFirst => "lapse_first",
+            DecrementOrder::MidMonth => "mid_month",
+        };
+
+        // Per policy rounding has no unit of sum insured
+        let rounding = self.premium_rules.rounding.map(|(basis, decimals)| {
+            let per_sum_insured = match basis {
+                RoundingBasis::PerPolicy => None,
+                RoundingBasis::PerSumInsured(unit) => Some(unit),
+            };
+            json!({"per_sum_insured": per_sum_insured, "decimals": decimals})
+        });
+
+        json!({
+            "name": self.name,
+            "reserve_floor": self.reserve_floor,
+            "premium_target": premium_target,
+            "commission": {"rates": commission_rates, "clawback": clawback},
+            "modal_loadings": self.modal_loadings,
+            "decrement_conversion": decrement_conversion,
+            "decrement_order": decrement_order,
+            "premium_rules": {
+                "rounding": rounding,
+                "policy_fee": self.premium_rules.policy_fee,
+                "minimum_premium": self.premium_rules.minimum_premium,
+            },
+            "tax": {
+                "premium_tax_rate": self.tax.premium_tax_rate,
+                "corporate_tax_rates": self.tax.corporate_tax_rates,
+            },
+            "reinsurance": {
+                "quota_share": self.reinsurance.quota_share,
+                "retention": self.reinsurance.retention,
+                "yrt_rates": self.reinsurance.yrt_rates,
+                "commission_rate": self.reinsurance.commission_rate,
+            },
+            "earned_rate": self.earned_rate,
+            "mass_lapse": self.mass_lapse,
+            "pricing_basis": self.pricing_basis.as_ref().map(|basis| basis.to_json()),
+        })
+    }
+
+    // Scenario saved by to_json
+    pub fn from_json(value: &Value) -> PolarsResult<Self> {
+        let mut scenario = Self::new_by_name(__str(value, "name")?)?;
+
+        scenario.reserve_floor = value["reserve_floor"]
+            .as_bool()
+            .ok_or_else(|| __json_error("reserve_floor"))?;
+
+        let target = &value["premium_target"];
+        scenario.premium_target = match target {
+            Value::Null => None,
+            _ => Some(match __str(target, "type")? {
+                "zero_profit" => PremiumTarget::ZeroProfit {
+                    risk_discount_rate: __f64(target, "risk_discount_rate")?,
+                },
+                "profit_margin" => PremiumTarget::ProfitMargin {
+                    margin: __f64(target, "margin")?,
+                    risk_discount_rate: __f64(target, "risk_discount_rate")?,
+                },
+                "irr" => PremiumTarget::Irr {
+                    hurdle_rate: __f64(target, "hurdle_rate")?,
+                },
+                _ => return Err(__json_error("premium_target")),
+            }),
+        };
+
+        let commission = &value["commission"];
+        let rates = commission["rates"]
+            .as_array()
+            .ok_or_else(|| __json_error("commission"))?;
+        let clawback = &commission["clawback"];
+        scenario.commission = CommissionScale {
+            rates: rates
+                .iter()
+                .map(|r| {
+                    Ok(CommissionRate {
+                        year: __i32(r, "year")?,
+                        rate: __f64(r, "rate")?,
+                        term: __opt(r, "term", __i32)?,
+                        channel: __opt(r, "channel", __str)?.map(String::from),
+                    })
+                })
+                .collect::<PolarsResult<Vec<CommissionRate>>>()?,
+            clawback: match clawback {
+                Value::Null => None,
+                _ => Some(Clawback {
+                    period_mth: __i32(clawback, "period_mth")?,
+                    rate: __f64(clawback, "rate")?,
+                }),
+            },
+        };
+
+        scenario.modal_loadings = __pairs(value, "modal_loadings")?;
+
+        scenario.decrement_conversion = match __str(value, "decrement_conversion")? {
+            "constant_force" => DecrementConversion::ConstantForce,
+            "udd" => DecrementConversion::Udd,
+            "divide12" => DecrementConversion::Divide12,
+            _ => return Err(__json_error("decrement_conversion")),
+        };
+        scenario.decrement_order = match __str(value, "decrement_order")? {
+            "death_first" => DecrementOrder::DeathFirst,
+            "lapse_first" => DecrementOrder::LapseFirst,
+            "mid_month" => DecrementOrder::MidMonth,
+            _ => return Err(__json_error("decrement_order")),
+        };
+
+        let rules = &value["premium_rules"];
+        let rounding = &rules["rounding"];
+        scenario.premium_rules = PremiumRules {
+            rounding: match rounding {
+                Value::Null => None,
+                _ => {
+                    let basis = match __opt(rounding, "per_sum_insured", __f64)? {
+                        Some(unit) => RoundingBasis::PerSumInsured(unit),
+                        None => RoundingBasis::PerPolicy,
+                    };
+                    Some((basis, __i32(rounding, "decimals")?))
+                }
+            },
+            policy_fee: __f64(rules, "policy_fee")?,
+            minimum_premium: __f64(rules, "minimum_premium")?,
+        };
+
+        let tax = &value["tax"];
+        scenario.tax = TaxBasis {
+            premium_tax_rate: __f64(tax, "premium_tax_rate")?,
+            corporate_tax_rates: __pairs(tax, "corporate_tax_rates")?,
+        };
+
+        let reinsurance = &value["reinsurance"];
+        scenario.reinsurance = ReinsuranceTreaty {
+            quota_share: __f64(reinsurance, "quota_share")?,
+            retention: __opt(reinsurance, "retention", __f64)?,
+            yrt_rates: __pairs(reinsurance, "yrt_rates")?,
+            commission_rate: __f64(reinsurance, "commission_rate")?,
+        };
+
+        scenario.earned_rate = __opt(value, "earned_rate", __f64)?;
+        scenario.mass_lapse = __f64(value, "mass_lapse")?;
+        scenario.pricing_basis = match &value["pricing_basis"] {
+            Value::Null => None,
+            basis => Some(Box::new(Self::from_json(basis)?)),
+        };
+
+        Ok(scenario)
+    }
 }
 
 //---------------------------------------------------------------------------------------------------------
@@ -119,6 +499,51 @@ fn _dataframe_to_vec_of_tuples(df: &DataFrame) -> PolarsResult<Vec<(String, Stri
     Ok(result)
 }
 
+// Saved scenario settings
+fn __json_error(key: &str) -> PolarsError {
+    PolarsError::ComputeError(format!("Invalid assumption scenario setting '{key}'").into())
+}
+
+fn __str<'a>(value: &'a Value, key: &str) -> PolarsResult<&'a str> {
+    value[key].as_str().ok_or_else(|| __json_error(key))
+}
+
+fn __f64(value: &Value, key: &str) -> PolarsResult<f64> {
+    value[key].as_f64().ok_or_else(|| __json_error(key))
+}
+
+fn __i32(value: &Value, key: &str) -> PolarsResult<i32> {
+    value[key]
+        .as_i64()
+        .map(|v| v as i32)
+        .ok_or_else(|| __json_error(key))
+}
+
+// Null for None
+fn __opt<'a, T>(
+    value: &'a Value,
+    key: &str,
+    parse: fn(&'a Value, &str) -> PolarsResult<T>,
+) -> PolarsResult<Option<T>> {
+    match value[key] {
+        Value::Null => Ok(None),
+        _ => parse(value, key).map(Some),
+    }
+}
+
+// List of (integer, rate) pairs, eg: tax rates by projection year
+fn __pairs(value: &Value, key: &str) -> PolarsResult<Vec<(i32, f64)>> {
+    value[key]
+        .as_array()
+        .ok_or_else(|| __json_error(key))?
+        .iter()
+        .map(|pair| match (pair[0].as_i64(), pair[1].as_f64()) {
+            (Some(k), Some(v)) => Ok((k as i32, v)),
+            _ => Err(__json_error(key)),
+        })
+        .collect()
+}
+
 //---------------------------------------------------------------------------------------------------------
 // UNIT TESTS
 //---------------------------------------------------------------------------------------------------------
@@ -133,7 +558,10 @@ mod tests {
 
         println!("{df:?}");
 
-        assert!(true, "Mortality DataFrame should be created successfully");
+        assert!(
+            df.is_ok(),
+            "Mortality DataFrame should be created successfully"
+        );
     }
 
     #[test]
@@ -154,6 +582,69 @@ mod tests {
 
         println!("{df:?}");
 
-        assert!(true, "AssumptionScenario should be created successfully");
+        assert!(
+            df.is_ok(),
+            "AssumptionScenario should be created successfully"
+        );
+    }
+
+    #[test]
+    fn test_method_assumption_scenario_json() {
+        let scenario = AssumptionScenario::new_by_name("pricing")
+            .unwrap()
+            .with_reserve_floor(true)
+            .with_premium_target(PremiumTarget::ProfitMargin {
+                margin: 0.05,
+                risk_discount_rate: 0.08,
+            })
+            .with_commission(
+                CommissionScale::new()
+                    .with_rate(1, 0.5)
+                    .with_term_channel_rate(2, 10, "agency", 0.1)
+                    .with_clawback(12, 1.0),
+            )
+            .with_modal_loading(12, 0.04)
+            .with_decrement_conversion(DecrementConversion::Udd)
+            .with_decrement_order(DecrementOrder::MidMonth)
+            .with_premium_rules(
+                PremiumRules::default()
+                    .with_rounding(RoundingBasis::PerSumInsured(1_000.0), 2)
+                    .with_policy_fee(120.0),
+            )
+            .with_tax(
+                TaxBasis::new()
+                    .with_premium_tax_rate(0.02)
+                    .with_corporate_tax_rate(1, 0.2),
+            )
+            .with_reinsurance(
+                ReinsuranceTreaty::new()
+                    .with_retention(200_000.0)
+                    .with_yrt_rate(0, 0.001),
+            )
+            .with_earned_rate(0.03)
+            .with_mass_lapse(0.1)
+            .with_pricing_basis(
+                AssumptionScenario::new_by_name("pricing")
+                    .unwrap()
+                    .with_mass_lapse(0.2),
+            );
+
+        let json = scenario.to_json();
+        let restored = AssumptionScenario::from_json(&json).unwrap();
+
+        println!("{json}");
+
+        assert_eq!(restored.to_json(), json);
+        assert_eq!(restored.premium_target, scenario.premium_target);
+        assert_eq!(restored.commission, scenario.commission);
+        assert_eq!(restored.premium_rules, scenario.premium_rules);
+        assert_eq!(restored.tax, scenario.tax);
+        assert_eq!(restored.reinsurance, scenario.reinsurance);
+        assert_eq!(restored.pricing_basis.unwrap().mass_lapse, 0.2);
+
+        // Unknown settings are rejected
+        let mut invalid = json.clone();
+        invalid["decrement_order"] = json!("random");
+        assert!(AssumptionScenario::from_json(&invalid).is_err());
     }
 }
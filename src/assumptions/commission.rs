@@ -0,0 +1,126 @@
+//---------------------------------------------------------------------------------------------------------
+// STRUCTS
+//---------------------------------------------------------------------------------------------------------
+// Commission rate as a share of premium for a policy year - term and channel narrow the rate down
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommissionRate {
+    pub year: i32,
+    pub rate: f64,
+    pub term: Option<i32>,
+    pub channel: Option<String>,
+}
+
+// Initial commission received on policies lapsing within the clawback period is repaid,
+// in proportion to the unexpired part of the period
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clawback {
+    pub period_mth: i32,
+    pub rate: f64,
+}
+
+// Commission paid in policy year 1 is initial commission, renewal commission afterwards
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommissionScale {
+    pub rates: Vec<CommissionRate>,
+    pub clawback: Option<Clawback>,
+}
+
+// Default scale is 100% of premium in the first policy year
+impl Default for CommissionScale {
+    fn default() -> Self {
+        Self::new().with_rate(1, 1.0)
+    }
+}
+
+#[allow(dead_code)]
+impl CommissionScale {
+    pub fn new() -> Self {
+        Self {
+            rates: Vec::new(),
+            clawback: None,
+        }
+    }
+
+    pub fn with_rate(self, year: i32, rate: f64) -> Self {
+        self._with(year, rate, None, None)
+    }
+
+    pub fn with_term_rate(self, year: i32, term: i32, rate: f64) -> Self {
+        self._with(year, rate, Some(term), None)
+    }
+
+    pub fn with_channel_rate(self, year: i32, channel: &str, rate: f64) -> Self {
+        self._with(year, rate, None, Some(channel.to_string()))
+    }
+
+    pub fn with_term_channel_rate(self, year: i32, term: i32, channel: &str, rate: f64) -> Self {
+        self._with(year, rate, Some(term), Some(channel.to_string()))
+    }
+
+    pub fn with_clawback(mut self, period_mth: i32, rate: f64) -> Self {
+        self.clawback = Some(Clawback { period_mth, rate });
+        self
+    }
+
+    // Most specific rate matching the policy - 0.0 if none is given for the year
+    pub fn rate(&self, year: i32, term: i32, channel: Option<&str>) -> f64 {
+        self.rates
+            .iter()
+            .filter(|r| r.year == year)
+            .filter(|r| r.term.is_none_or(|t| t == term))
+            .filter(|r| r.channel.is_none() || r.channel.as_deref() == channel)
+            .max_by_key(|r| (r.term.is_some() as i32) + (r.channel.is_some() as i32))
+            .map(|r| r.rate)
+            .unwrap_or(0.0)
+    }
+
+    // Share of the initial commission repaid on lapse in the given policy month
+    pub fn clawback_rate(&self, policy_mth: i32) -> f64 {
+        match &self.clawback {
+            Some(c) if policy_mth < c.period_mth => {
+                c.rate * (c.period_mth - policy_mth) as f64 / c.period_mth as f64
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn _with(mut self, year: i32, rate: f64, term: Option<i32>, channel: Option<String>) -> Self {
+        self.rates.push(CommissionRate {
+            year,
+            rate,
+            term,
+            channel,
+        });
+        self
+    }
+}
+
+//---------------------------------------------------------------------------------------------------------
+// UNIT TESTS
+//---------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_method_commission_scale_rate() {
+        let scale = CommissionScale::new()
+            .with_rate(1, 0.5)
+            .with_rate(2, 0.05)
+            .with_term_rate(1, 20, 0.8)
+            .with_channel_rate(1, "broker", 0.6)
+            .with_term_channel_rate(1, 20, "broker", 1.0)
+            .with_clawback(24, 1.0);
+
+        assert_eq!(scale.rate(1, 10, None), 0.5);
+        assert_eq!(scale.rate(1, 20, None), 0.8);
+        assert_eq!(scale.rate(1, 10, Some("broker")), 0.6);
+        assert_eq!(scale.rate(1, 20, Some("broker")), 1.0);
+        assert_eq!(scale.rate(2, 20, Some("broker")), 0.05);
+        assert_eq!(scale.rate(3, 20, Some("broker")), 0.0);
+
+        assert_eq!(scale.clawback_rate(0), 1.0);
+        assert_eq!(scale.clawback_rate(12), 0.5);
+        assert_eq!(scale.clawback_rate(24), 0.0);
+    }
+}
@@ -0,0 +1,107 @@
+//---------------------------------------------------------------------------------------------------------
+// STRUCTS
+//---------------------------------------------------------------------------------------------------------
+// Unit the premium is rounded in
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RoundingBasis {
+    PerPolicy,
+    PerSumInsured(f64), // Premium rate per the given amount of sum insured, e.g. per 1,000
+}
+
+// Construction of the published premium from the calculated premium per installment:
+// rounded rate, plus the policy fee, subject to the minimum premium
+#[derive(Debug, Clone, PartialEq)]
+pub struct PremiumRules {
+    pub rounding: Option<(RoundingBasis, i32)>, // Basis and number of decimals - no rounding if None
+    pub policy_fee: f64,                        // Annual fee, spread over the installments
+    pub minimum_premium: f64,                   // Annual minimum, including the policy fee
+}
+
+// Default rules leave the calculated premium unchanged
+impl Default for PremiumRules {
+    fn default() -> Self {
+        Self {
+            rounding: None,
+            policy_fee: 0.0,
+            minimum_premium: 0.0,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl PremiumRules {
+    pub fn with_rounding(mut self, basis: RoundingBasis, decimals: i32) -> Self {
+        self.rounding = Some((basis, decimals));
+        self
+    }
+
+    pub fn with_policy_fee(mut self, policy_fee: f64) -> Self {
+        self.policy_fee = policy_fee;
+        self
+    }
+
+    pub fn with_minimum_premium(mut self, minimum_premium: f64) -> Self {
+        self.minimum_premium = minimum_premium;
+        self
+    }
+
+    // Share of the annual policy fee charged with each installment
+    pub fn installment_fee(&self, payment_freq: i32) -> f64 {
+        self.policy_fee / payment_freq as f64
+    }
+
+    // Published premium per installment for a policy
+    pub fn apply(&self, premium: f64, sum_insured: f64, payment_freq: i32) -> f64 {
+        let rounded = match self.rounding {
+            Some((RoundingBasis::PerPolicy, decimals)) => _round(premium, decimals),
+            Some((RoundingBasis::PerSumInsured(unit), decimals)) if sum_insured != 0.0 => {
+                let units = sum_insured / unit;
+                _round(premium / units, decimals) * units
+            }
+            _ => premium,
+        };
+
+        let freq = payment_freq as f64;
+        (rounded + self.installment_fee(payment_freq)).max(self.minimum_premium / freq)
+    }
+}
+
+//---------------------------------------------------------------------------------------------------------
+// PRIVATE
+//---------------------------------------------------------------------------------------------------------
+fn _round(value: f64, decimals: i32) -> f64 {
+    let scale = 10f64.powi(decimals);
+    (value * scale).round() / scale
+}
+
+//---------------------------------------------------------------------------------------------------------
+// UNIT TESTS
+//---------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_method_premium_rules_apply() {
+        assert_eq!(
+            PremiumRules::default().apply(123.4567, 100_000.0, 12),
+            123.4567
+        );
+
+        // 1.234567 per 1,000 rounds to 1.23
+        let per_mille =
+            PremiumRules::default().with_rounding(RoundingBasis::PerSumInsured(1000.0), 2);
+        assert!((per_mille.apply(123.4567, 100_000.0, 1) - 123.0).abs() < 1e-9);
+
+        let rules = PremiumRules::default()
+            .with_rounding(RoundingBasis::PerPolicy, 2)
+            .with_policy_fee(60.0)
+            .with_minimum_premium(240.0);
+        assert!((rules.apply(123.4567, 100_000.0, 12) - 128.46).abs() < 1e-9);
+
+        // Minimum premium applies per installment
+        assert!((rules.apply(123.4567, 100_000.0, 1) - 240.0).abs() < 1e-9);
+        assert!((rules.apply(10.0, 100_000.0, 12) - 20.0).abs() < 1e-9);
+    }
+}
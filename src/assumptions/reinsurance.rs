@@ -0,0 +1,87 @@
+//---------------------------------------------------------------------------------------------------------
+// STRUCTS
+//---------------------------------------------------------------------------------------------------------
+// Risk premium (YRT) treaty: a quota share of the sum at risk is ceded, then any retained amount above
+// the surplus retention limit. Reinsurance premiums are charged on the ceded sum at risk by attained age.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReinsuranceTreaty {
+    pub quota_share: f64,           // Share of the sum at risk ceded
+    pub retention: Option<f64>,     // Surplus retention limit - no surplus cession if None
+    pub yrt_rates: Vec<(i32, f64)>, // Annual rate per unit ceded, from the given age
+    pub commission_rate: f64,       // Reinsurance commission as a share of reinsurance premiums
+}
+
+#[allow(dead_code)]
+impl ReinsuranceTreaty {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_quota_share(mut self, quota_share: f64) -> Self {
+        self.quota_share = quota_share;
+        self
+    }
+
+    pub fn with_retention(mut self, retention: f64) -> Self {
+        self.retention = Some(retention);
+        self
+    }
+
+    pub fn with_yrt_rate(mut self, from_age: i32, rate: f64) -> Self {
+        self.yrt_rates.retain(|(age, _)| *age != from_age);
+        self.yrt_rates.push((from_age, rate));
+        self
+    }
+
+    pub fn with_commission_rate(mut self, commission_rate: f64) -> Self {
+        self.commission_rate = commission_rate;
+        self
+    }
+
+    // Amount ceded out of the sum at risk of a policy
+    pub fn ceded(&self, sum_at_risk: f64) -> f64 {
+        let quota_share = self.quota_share * sum_at_risk;
+        let surplus = match self.retention {
+            Some(retention) => (sum_at_risk - quota_share - retention).max(0.0),
+            None => 0.0,
+        };
+        quota_share + surplus
+    }
+
+    // Rate of the highest age band at or below the attained age - nil below the first band
+    pub fn yrt_rate(&self, age: i32) -> f64 {
+        self.yrt_rates
+            .iter()
+            .filter(|(from_age, _)| *from_age <= age)
+            .max_by_key(|(from_age, _)| *from_age)
+            .map(|(_, rate)| *rate)
+            .unwrap_or(0.0)
+    }
+}
+
+//---------------------------------------------------------------------------------------------------------
+// UNIT TESTS
+//---------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_method_reinsurance_treaty_ceded() {
+        let treaty = ReinsuranceTreaty::new()
+            .with_quota_share(0.2)
+            .with_retention(50_000.0)
+            .with_yrt_rate(20, 0.001)
+            .with_yrt_rate(50, 0.004);
+
+        assert_eq!(ReinsuranceTreaty::new().ceded(100_000.0), 0.0);
+
+        // 20,000 quota share then 30,000 above the retention
+        assert_eq!(treaty.ceded(100_000.0), 50_000.0);
+        assert_eq!(treaty.ceded(50_000.0), 10_000.0);
+
+        assert_eq!(treaty.yrt_rate(18), 0.0);
+        assert_eq!(treaty.yrt_rate(49), 0.001);
+        assert_eq!(treaty.yrt_rate(65), 0.004);
+    }
+}
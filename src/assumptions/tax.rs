@@ -0,0 +1,60 @@
+//---------------------------------------------------------------------------------------------------------
+// STRUCTS
+//---------------------------------------------------------------------------------------------------------
+// Premium tax on premiums received, corporation tax on profits after losses carried forward
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TaxBasis {
+    pub premium_tax_rate: f64,
+    pub corporate_tax_rates: Vec<(i32, f64)>, // Rate applying from the given projection year onward
+}
+
+#[allow(dead_code)]
+impl TaxBasis {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_premium_tax_rate(mut self, premium_tax_rate: f64) -> Self {
+        self.premium_tax_rate = premium_tax_rate;
+        self
+    }
+
+    pub fn with_corporate_tax_rate(mut self, from_year: i32, rate: f64) -> Self {
+        self.corporate_tax_rates
+            .retain(|(year, _)| *year != from_year);
+        self.corporate_tax_rates.push((from_year, rate));
+        self
+    }
+
+    // Rate of the latest change at or before the projection year (starting at 1) - nil before any
+    pub fn corporate_tax_rate(&self, year: i32) -> f64 {
+        self.corporate_tax_rates
+            .iter()
+            .filter(|(from_year, _)| *from_year <= year)
+            .max_by_key(|(from_year, _)| *from_year)
+            .map(|(_, rate)| *rate)
+            .unwrap_or(0.0)
+    }
+}
+
+//---------------------------------------------------------------------------------------------------------
+// UNIT TESTS
+//---------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_method_tax_basis_corporate_tax_rate() {
+        let tax = TaxBasis::new()
+            .with_corporate_tax_rate(1, 0.25)
+            .with_corporate_tax_rate(5, 0.2)
+            .with_corporate_tax_rate(5, 0.18);
+
+        assert_eq!(TaxBasis::new().corporate_tax_rate(1), 0.0);
+        assert_eq!(tax.corporate_tax_rate(1), 0.25);
+        assert_eq!(tax.corporate_tax_rate(4), 0.25);
+        assert_eq!(tax.corporate_tax_rate(5), 0.18);
+        assert_eq!(tax.corporate_tax_rate(30), 0.18);
+    }
+}
@@ -1,5 +1,7 @@
 mod assumptions;
+mod mp_compression;
 mod mp_gen;
+mod mp_profile;
 mod projections;
 
 use crate::assumptions::assumption_scenario::AssumptionScenario;
@@ -16,17 +18,17 @@ use std::time::Instant;
 fn get_run_setups() -> PolarsResult<RunsSetup> {
     let model_points_df = generate_s_model_points()?;
 
-    let run_setup_01 = SingleRunSetup {
-        description: "Run setup 01 - Used for pricing".to_string(),
-        model_points_df: model_points_df.clone(),
-        assumption_scenario: AssumptionScenario::new_by_name("pricing")?,
-    };
-
-    let run_setup_02 = SingleRunSetup {
-        description: "Run setup 02 - Used for valuation".to_string(),
-        model_points_df: model_points_df.clone(),
-        assumption_scenario: AssumptionScenario::new_by_name("valuation")?,
-    };
+    let run_setup_01 = SingleRunSetup::new(
+        "Run setup 01 - Used for pricing",
+        model_points_df.clone(),
+        AssumptionScenario::new_by_name("pricing")?,
+    );
+
+    let run_setup_02 = SingleRunSetup::new(
+        "Run setup 02 - Used for valuation",
+        model_points_df.clone(),
+        AssumptionScenario::new_by_name("valuation")?,
+    );
 
     let result = RunsSetup {
         description: "Runs setup for pricing and valuation".to_string(),
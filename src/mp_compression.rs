@@ -0,0 +1,61 @@
+use crate::assumptions::assumption_scenario::AssumptionScenario;
+use crate::projections::projection_single_run::{OutputProfile, PV_ITEMS, SingleRunSetup};
+use polars::prelude::*;
+
+pub mod cluster_compression;
+pub mod compression_report;
+pub mod grid_compression;
+
+//---------------------------------------------------------------------------------------------------------
+// PRIVATE
+//---------------------------------------------------------------------------------------------------------
+// Columns combined over the model points of a cell or cluster
+const AGGREGATED_COLUMNS: [&str; 4] = ["id", "entry_age", "policy_count", "sum_insured"];
+
+// Every other column (eg: model, gender, duration_mth, payment_freq, channel, benefit_pattern) splits
+// the model points into segments and is carried through, so compressed points keep their product schema
+fn _segment_columns(df: &DataFrame, compressed: &[String]) -> PolarsResult<Vec<String>> {
+    if df.column("model").is_err() {
+        return Err(PolarsError::ColumnNotFound(
+            "Column 'model' is needed to compress model points".into(),
+        ));
+    }
+
+    Ok(df
+        .get_column_names()
+        .iter()
+        .map(|c| c.to_string())
+        .filter(|c| !AGGREGATED_COLUMNS.contains(&c.as_str()) && !compressed.contains(c))
+        .collect())
+}
+
+// Sort representative points, give them new ids and keep the seriatim column order in front
+fn _finalize_compressed_df(
+    df: DataFrame,
+    seriatim_df: &DataFrame,
+    segment_columns: &[String],
+) -> PolarsResult<DataFrame> {
+    let sort_cols: Vec<String> = segment_columns
+        .iter()
+        .cloned()
+        .chain(["entry_age".to_string()])
+        .collect();
+    let mut df = df.sort(
+        sort_cols,
+        SortMultipleOptions::default().with_maintain_order(true),
+    )?;
+
+    let id = Series::new("id".into(), (1..=df.height() as i32).collect::<Vec<i32>>());
+    df.with_column(id)?;
+
+    let columns: Vec<String> = seriatim_df
+        .get_column_names()
+        .iter()
+        .map(|c| c.to_string())
+        .chain([
+            "total_sum_insured".to_string(),
+            "seriatim_count".to_string(),
+        ])
+        .collect();
+    df.select(columns)
+}
@@ -0,0 +1,266 @@
+use super::*;
+use ndarray::prelude::*;
+use ndarray_rand::rand::rngs::StdRng;
+use ndarray_rand::rand::{Rng, SeedableRng};
+
+//---------------------------------------------------------------------------------------------------------
+// STRUCTS
+//---------------------------------------------------------------------------------------------------------
+/*
+Cluster model points in the style of lifelib's cluster_model_points:
+- K-means on standardised calibration columns (any numeric columns, eg: joined PVs of cashflows)
+- The representative of each cluster is the actual model point closest to its centre
+- Clustering is performed separately for each segment (model, gender and any other column that is
+  neither aggregated nor a calibration column, eg: payment_freq) so that these are never mixed
+*/
+#[derive(Clone, Debug)]
+pub struct ClusterCompression {
+    pub n_clusters: usize, // Target number of representative points across all segments
+    pub features: Vec<String>, // Calibration columns used to measure distance
+    pub max_iter: usize,
+    pub seed: u64,
+}
+
+impl Default for ClusterCompression {
+    fn default() -> Self {
+        Self {
+            n_clusters: 100,
+            features: vec![
+                "entry_age".to_string(),
+                "term".to_string(),
+                "sum_insured".to_string(),
+            ],
+            max_iter: 100,
+            seed: 42,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl ClusterCompression {
+    pub fn compress(&self, model_points_df: &DataFrame) -> PolarsResult<DataFrame> {
+        if self.n_clusters == 0 {
+            return Err(PolarsError::ComputeError(
+                "Number of clusters must be at least 1".into(),
+            ));
+        }
+
+        let total_count = model_points_df.height();
+        let segment_columns = _segment_columns(model_points_df, &self.features)?;
+        let segments = model_points_df.partition_by_stable(&segment_columns, true)?;
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+
+        let mut rep_dfs = Vec::with_capacity(segments.len());
+
+        for segment_df in segments.iter() {
+            // Allocate clusters to each segment in proportion to its size
+            let k = ((self.n_clusters * segment_df.height()) as f64 / total_count as f64).round()
+                as usize;
+            let k = k.clamp(1, segment_df.height());
+
+            let features = _standardised_features(segment_df, &self.features)?;
+            let labels = _k_means(&features, k, self.max_iter, &mut rng);
+
+            rep_dfs.push(_representatives(segment_df, &features, &labels, k)?);
+        }
+
+        let mut df = DataFrame::empty();
+        for rep_df in rep_dfs.iter() {
+            df.vstack_mut(rep_df)?;
+        }
+
+        _finalize_compressed_df(df, model_points_df, &segment_columns)
+    }
+}
+
+//---------------------------------------------------------------------------------------------------------
+// PRIVATE
+//---------------------------------------------------------------------------------------------------------
+fn _standardised_features(df: &DataFrame, features: &[String]) -> PolarsResult<Array2<f64>> {
+    let mut x = df
+        .select(features.iter().map(|s| s.as_str()))?
+        .to_ndarray::<Float64Type>(IndexOrder::C)?;
+
+    // Scale each column to zero mean and unit variance so no feature dominates the distance
+    for mut column in x.columns_mut() {
+        let mean = column.mean().unwrap_or(0.0);
+        let std = column.std(0.0);
+        column.mapv_inplace(|v| if std > 0.0 { (v - mean) / std } else { 0.0 });
+    }
+
+    Ok(x)
+}
+
+fn __squared_distance(a: ArrayView1<f64>, b: ArrayView1<f64>) -> f64 {
+    a.iter().zip(b.iter()).map(|(x, y)| (x - y).powi(2)).sum()
+}
+
+fn __nearest_centre(point: ArrayView1<f64>, centres: &Array2<f64>) -> usize {
+    centres
+        .rows()
+        .into_iter()
+        .map(|centre| __squared_distance(point, centre))
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(idx, _)| idx)
+        .unwrap_or(0)
+}
+
+// K-means with k-means++ initialisation - returns the cluster label of each row
+fn _k_means(x: &Array2<f64>, k: usize, max_iter: usize, rng: &mut StdRng) -> Vec<usize> {
+    let n = x.nrows();
+
+    // K-means++ initialisation: pick each next centre with probability proportional to squared distance
+    let mut centres = Array2::<f64>::zeros((k, x.ncols()));
+    centres.row_mut(0).assign(&x.row(rng.gen_range(0..n)));
+
+    for c in 1..k {
+        let distances: Vec<f64> = x
+            .rows()
+            .into_iter()
+            .map(|row| {
+                let nearest = __nearest_centre(row, &centres.slice(s![..c, ..]).to_owned());
+                __squared_distance(row, centres.row(nearest))
+            })
+            .collect();
+
+        let total: f64 = distances.iter().sum();
+        let mut target = rng.r#gen::<f64>() * total;
+        let mut chosen = n - 1;
+        for (idx, d) in distances.iter().enumerate() {
+            target -= d;
+            if target <= 0.0 && *d > 0.0 {
+                chosen = idx;
+                break;
+            }
+        }
+        centres.row_mut(c).assign(&x.row(chosen));
+    }
+
+    // Lloyd iterations
+    let mut labels = vec![0; n];
+    for iter in 0..max_iter {
+        let new_labels: Vec<usize> = x
+            .rows()
+            .into_iter()
+            .map(|row| __nearest_centre(row, &centres))
+            .collect();
+
+        if iter > 0 && new_labels == labels {
+            break;
+        }
+        labels = new_labels;
+
+        for c in 0..k {
+            let members: Vec<usize> = (0..n).filter(|&i| labels[i] == c).collect();
+            if !members.is_empty() {
+                let centre = x.select(Axis(0), &members).mean_axis(Axis(0)).unwrap();
+                centres.row_mut(c).assign(&centre);
+            }
+        }
+    }
+
+    labels
+}
+
+// Pick the closest actual model point to each cluster centre and carry the cluster weights - the other
+// columns are those of the representative
+fn _representatives(
+    df: &DataFrame,
+    x: &Array2<f64>,
+    labels: &[usize],
+    k: usize,
+) -> PolarsResult<DataFrame> {
+    let policy_count = df.column("policy_count")?.cast(&DataType::Float64)?;
+    let sum_insured = df.column("sum_insured")?.cast(&DataType::Float64)?;
+    let (policy_count, sum_insured) = (policy_count.f64()?, sum_insured.f64()?);
+
+    let mut rep_rows: Vec<IdxSize> = Vec::with_capacity(k);
+    let mut rep_policy_count = Vec::with_capacity(k);
+    let mut rep_total_sum_insured = Vec::with_capacity(k);
+    let mut rep_seriatim_count = Vec::with_capacity(k);
+
+    for c in 0..k {
+        let members: Vec<usize> = (0..labels.len()).filter(|&i| labels[i] == c).collect();
+        if members.is_empty() {
+            continue;
+        }
+
+        let centre = x.select(Axis(0), &members).mean_axis(Axis(0)).unwrap();
+        let rep = *members
+            .iter()
+            .min_by(|&&a, &&b| {
+                __squared_distance(x.row(a), centre.view())
+                    .total_cmp(&__squared_distance(x.row(b), centre.view()))
+            })
+            .unwrap();
+
+        rep_rows.push(rep as IdxSize);
+        rep_policy_count.push(
+            members
+                .iter()
+                .map(|&i| policy_count.get(i).unwrap_or_default())
+                .sum::<f64>(),
+        );
+        rep_total_sum_insured.push(
+            members
+                .iter()
+                .map(|&i| {
+                    policy_count.get(i).unwrap_or_default() * sum_insured.get(i).unwrap_or_default()
+                })
+                .sum::<f64>(),
+        );
+        rep_seriatim_count.push(members.len() as i32);
+    }
+
+    let rep_sum_insured: Vec<f64> = rep_total_sum_insured
+        .iter()
+        .zip(rep_policy_count.iter())
+        .map(|(si, count)| if *count != 0.0 { si / count } else { 0.0 })
+        .collect();
+
+    let mut rep_df = df.take(&IdxCa::from_vec("rep".into(), rep_rows))?;
+    rep_df.with_column(Series::new("policy_count".into(), rep_policy_count))?;
+    rep_df.with_column(Series::new("sum_insured".into(), rep_sum_insured))?;
+    rep_df.with_column(Series::new(
+        "total_sum_insured".into(),
+        rep_total_sum_insured,
+    ))?;
+    rep_df.with_column(Series::new("seriatim_count".into(), rep_seriatim_count))?;
+
+    Ok(rep_df)
+}
+
+//---------------------------------------------------------------------------------------------------------
+// UNIT TESTS
+//---------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp_gen::s_mp_gen::generate_s_model_points;
+
+    #[test]
+    fn test_method_cluster_compression_compress() {
+        let seriatim_df = generate_s_model_points(500, 42).unwrap();
+        let compression = ClusterCompression {
+            n_clusters: 20,
+            ..Default::default()
+        };
+        let compressed_df = compression.compress(&seriatim_df).unwrap();
+
+        println!("{compressed_df:?}");
+
+        assert!(compressed_df.height() <= 20);
+
+        let seriatim_count = compressed_df
+            .column("seriatim_count")
+            .unwrap()
+            .i32()
+            .unwrap();
+        assert_eq!(seriatim_count.sum(), Some(500));
+
+        let policy_count = compressed_df.column("policy_count").unwrap().f64().unwrap();
+        assert_eq!(policy_count.sum(), Some(500.0));
+    }
+}
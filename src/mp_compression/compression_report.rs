@@ -0,0 +1,86 @@
+use super::*;
+
+//---------------------------------------------------------------------------------------------------------
+// PUBLIC
+//---------------------------------------------------------------------------------------------------------
+// Project both the seriatim and compressed model points and compare the PV of key cashflows
+#[allow(dead_code)]
+pub fn compression_report(
+    seriatim_df: &DataFrame,
+    compressed_df: &DataFrame,
+    assumption_scenario: &AssumptionScenario,
+) -> PolarsResult<DataFrame> {
+    let seriatim_pv = _pv_totals(seriatim_df, assumption_scenario)?;
+    let compressed_pv = _pv_totals(compressed_df, assumption_scenario)?;
+
+    let items: Vec<&str> = PV_ITEMS.iter().map(|(_, pv_name)| *pv_name).collect();
+
+    let df = df![
+        "item" => items,
+        "seriatim" => seriatim_pv,
+        "compressed" => compressed_pv,
+    ]?
+    .lazy()
+    .with_column((col("compressed") - col("seriatim")).alias("diff"))
+    .with_column(
+        when(col("seriatim").neq(lit(0.0)))
+            .then(col("diff") / col("seriatim"))
+            .otherwise(lit(0.0))
+            .alias("diff_pct"),
+    )
+    .collect()?;
+
+    Ok(df)
+}
+
+//---------------------------------------------------------------------------------------------------------
+// PRIVATE
+//---------------------------------------------------------------------------------------------------------
+fn _pv_totals(
+    model_points_df: &DataFrame,
+    assumption_scenario: &AssumptionScenario,
+) -> PolarsResult<Vec<f64>> {
+    let setup = SingleRunSetup::new(
+        "Compression report",
+        model_points_df.clone(),
+        assumption_scenario.clone(),
+    )
+    .with_output_profile(OutputProfile::PvOnly);
+
+    let pv_total_df = setup.projection_run()?.pv_total_df;
+
+    PV_ITEMS
+        .iter()
+        .map(|(_, pv_name)| Ok(pv_total_df.column(pv_name)?.f64()?.get(0).unwrap_or(0.0)))
+        .collect()
+}
+
+//---------------------------------------------------------------------------------------------------------
+// UNIT TESTS
+//---------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp_compression::grid_compression::GridCompression;
+    use crate::mp_gen::s_mp_gen::generate_s_model_points;
+
+    #[test]
+    fn test_fn_compression_report() {
+        let seriatim_df = generate_s_model_points(50, 42).unwrap();
+        let compressed_df = GridCompression::default().compress(&seriatim_df).unwrap();
+        let assumption_scenario = AssumptionScenario::new_by_name("pricing").unwrap();
+
+        let report =
+            compression_report(&seriatim_df, &compressed_df, &assumption_scenario).unwrap();
+
+        println!("{report:?}");
+
+        assert_eq!(report.height(), PV_ITEMS.len());
+
+        // Compressed present values stay within 1% of the seriatim ones
+        let diff_pct = report.column("diff_pct").unwrap().f64().unwrap();
+        for (item, pct) in PV_ITEMS.iter().zip(diff_pct.into_no_null_iter()) {
+            assert!(pct.abs() < 0.01, "{}: {pct}", item.1);
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use super::*;
+
+//---------------------------------------------------------------------------------------------------------
+// STRUCTS
+//---------------------------------------------------------------------------------------------------------
+// Bucket model points by age band and every segment column (model, term, gender, ...) - one
+// representative point per cell
+#[derive(Clone, Debug)]
+pub struct GridCompression {
+    pub age_band_width: i32, // Width of each entry age band in years
+}
+
+impl Default for GridCompression {
+    fn default() -> Self {
+        Self { age_band_width: 5 }
+    }
+}
+
+#[allow(dead_code)]
+impl GridCompression {
+    pub fn compress(&self, model_points_df: &DataFrame) -> PolarsResult<DataFrame> {
+        if self.age_band_width < 1 {
+            return Err(PolarsError::ComputeError(
+                "Age band width must be at least 1 year".into(),
+            ));
+        }
+
+        let segment_columns = _segment_columns(model_points_df, &[])?;
+        let mut keys = vec![col("age_band")];
+        keys.extend(segment_columns.iter().map(|c| col(c.as_str())));
+
+        let df = model_points_df
+            .clone()
+            .lazy()
+            .with_columns(vec![
+                col("entry_age").cast(DataType::Int32),
+                col("policy_count").cast(DataType::Float64),
+                col("sum_insured").cast(DataType::Float64),
+            ])
+            .with_columns(vec![
+                // Lower bound of the entry age band
+                ((col("entry_age") / lit(self.age_band_width)) * lit(self.age_band_width))
+                    .alias("age_band"),
+                (col("sum_insured") * col("policy_count")).alias("total_sum_insured"),
+                (col("entry_age").cast(DataType::Float64) * col("policy_count"))
+                    .alias("weighted_age"),
+            ])
+            .group_by(keys)
+            .agg([
+                col("policy_count").sum(),
+                col("total_sum_insured").sum(),
+                col("weighted_age").sum(),
+                col("entry_age")
+                    .count()
+                    .cast(DataType::Int32)
+                    .alias("seriatim_count"),
+            ])
+            .with_columns(vec![
+                // Representative age is the policy weighted average age within the band
+                ((col("weighted_age") / col("policy_count")) + lit(0.5))
+                    .cast(DataType::Int32)
+                    .alias("entry_age"),
+                // Sum insured per policy so that the total sum insured of the cell is preserved
+                (col("total_sum_insured") / col("policy_count")).alias("sum_insured"),
+            ])
+            .collect()?;
+
+        _finalize_compressed_df(df, model_points_df, &segment_columns)
+    }
+}
+
+//---------------------------------------------------------------------------------------------------------
+// UNIT TESTS
+//---------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp_compression::cluster_compression::ClusterCompression;
+    use crate::mp_gen::s_mp_gen::generate_s_model_points;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_method_grid_compression_compress() {
+        let seriatim_df = generate_s_model_points(1000, 42).unwrap();
+        let compressed_df = GridCompression::default().compress(&seriatim_df).unwrap();
+
+        println!("{compressed_df:?}");
+
+        // 8 age bands x 3 terms x 2 genders at most
+        assert!(compressed_df.height() <= 48);
+
+        let total = |df: &DataFrame, name: &str| df.column(name).unwrap().f64().unwrap().sum();
+        let seriatim_si = (seriatim_df.column("sum_insured").unwrap().f64().unwrap()
+            * seriatim_df.column("policy_count").unwrap().f64().unwrap())
+        .sum()
+        .unwrap();
+
+        assert_eq!(
+            total(&compressed_df, "policy_count"),
+            total(&seriatim_df, "policy_count")
+        );
+        assert!((total(&compressed_df, "total_sum_insured").unwrap() - seriatim_si).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_method_grid_compression_keeps_product_columns() {
+        let issue_date = NaiveDate::from_ymd_opt(2020, 3, 15).unwrap();
+        let seriatim_df = df![
+            "model" => ["asl_se_model"; 4],
+            "id" => [1, 2, 3, 4],
+            "entry_age" => [30, 31, 32, 33],
+            "gender" => ["M"; 4],
+            "term" => [10; 4],
+            "policy_count" => [1.0, 2.0, 1.0, 2.0],
+            "sum_insured" => [100_000.0, 200_000.0, 100_000.0, 200_000.0],
+            "duration_mth" => [18; 4],
+            "issue_date" => [issue_date; 4],
+            "payment_freq" => [12, 12, 1, 1],
+            "payment_term" => [10; 4],
+        ]
+        .unwrap();
+
+        let compressed_df = GridCompression::default().compress(&seriatim_df).unwrap();
+
+        println!("{compressed_df:?}");
+
+        // Payment frequencies are never mixed and the product columns are carried through
+        assert_eq!(compressed_df.height(), 2);
+        let payment_freq = compressed_df.column("payment_freq").unwrap().i32().unwrap();
+        assert_eq!(
+            payment_freq.into_no_null_iter().collect::<Vec<i32>>(),
+            [1, 12]
+        );
+
+        // Clustering segments the model points the same way
+        let clustered_df = ClusterCompression {
+            n_clusters: 1,
+            ..Default::default()
+        }
+        .compress(&seriatim_df)
+        .unwrap();
+        assert_eq!(clustered_df.height(), 2);
+        assert!(
+            clustered_df
+                .column("issue_date")
+                .unwrap()
+                .equals(compressed_df.column("issue_date").unwrap())
+        );
+
+        let result = SingleRunSetup::new(
+            "Compressed ASL SE",
+            compressed_df,
+            AssumptionScenario::new_by_name("pricing").unwrap(),
+        )
+        .projection_run();
+        assert!(result.is_ok());
+    }
+}
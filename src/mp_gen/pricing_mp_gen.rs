@@ -1,34 +1,281 @@
 use super::*;
-use itertools::iproduct;
+use itertools::{Itertools, iproduct};
 
-pub fn generate_s_model_points() -> PolarsResult<DataFrame> {
-    let age: Vec<i32> = (20..60).collect();
-    let term: Vec<i32> = vec![10, 15, 20];
-    let gender: Vec<&str> = vec!["M", "F"];
+//---------------------------------------------------------------------------------------------------------
+// STRUCTS
+//---------------------------------------------------------------------------------------------------------
+// Pricing grid: every combination of the dimensions below becomes one model point
+#[derive(Clone, Debug)]
+pub struct PricingGrid {
+    pub model: String,
+    pub ages: Vec<i32>,
+    pub terms: Vec<i32>,
+    pub genders: Vec<String>,
+    pub sum_insureds: Vec<f64>,
+    pub extra_dimensions: Vec<(String, Vec<String>)>, // Additional rating factors, eg: smoker status
+}
+
+// Default grid is the standard S model pricing grid
+impl Default for PricingGrid {
+    fn default() -> Self {
+        Self::new("s_model")
+            .with_age_range(20, 59)
+            .with_terms(&[10, 15, 20])
+            .with_genders(&["M", "F"])
+            .with_sum_insureds(&[1000.0])
+    }
+}
+
+// Columns every grid generates - extra dimensions cannot take their names
+const BASE_COLUMNS: [&str; 7] = [
+    "model",
+    "id",
+    "entry_age",
+    "term",
+    "gender",
+    "policy_count",
+    "sum_insured",
+];
+
+#[allow(dead_code)]
+impl PricingGrid {
+    pub fn new(model: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            ages: Vec::new(),
+            terms: Vec::new(),
+            genders: Vec::new(),
+            sum_insureds: Vec::new(),
+            extra_dimensions: Vec::new(),
+        }
+    }
+
+    // Inclusive age range - can be called several times to add disjoint ranges
+    pub fn with_age_range(mut self, start: i32, end: i32) -> Self {
+        self.ages.extend(start..=end);
+        self
+    }
+
+    pub fn with_ages(mut self, ages: &[i32]) -> Self {
+        self.ages.extend_from_slice(ages);
+        self
+    }
+
+    pub fn with_terms(mut self, terms: &[i32]) -> Self {
+        self.terms.extend_from_slice(terms);
+        self
+    }
+
+    pub fn with_genders(mut self, genders: &[&str]) -> Self {
+        self.genders.extend(genders.iter().map(|g| g.to_string()));
+        self
+    }
+
+    pub fn with_sum_insureds(mut self, sum_insureds: &[f64]) -> Self {
+        self.sum_insureds.extend_from_slice(sum_insureds);
+        self
+    }
 
-    // Generate all combinations of age, term and gender
-    let combinations: Vec<(i32, i32, &str)> = iproduct!(age.iter(), term.iter(), gender.iter())
-        .map(|(&a, &t, &g)| (a, t, g))
+    pub fn with_dimension(mut self, name: &str, values: &[&str]) -> Self {
+        let values = values.iter().map(|v| v.to_string()).collect();
+        self.extra_dimensions.push((name.to_string(), values));
+        self
+    }
+
+    pub fn model_points_count(&self) -> usize {
+        let extra_count: usize = self.extra_dimensions.iter().map(|(_, v)| v.len()).product();
+        self.ages.len()
+            * self.terms.len()
+            * self.genders.len()
+            * self.sum_insureds.len()
+            * extra_count
+    }
+
+    pub fn generate(&self) -> PolarsResult<DataFrame> {
+        self._validate()?;
+
+        // Base dimensions, then every combination of the extra rating dimensions
+        let base: Vec<(i32, i32, &str, f64)> = iproduct!(
+            self.ages.iter(),
+            self.terms.iter(),
+            self.genders.iter(),
+            self.sum_insureds.iter()
+        )
+        .map(|(&a, &t, g, &s)| (a, t, g.as_str(), s))
         .collect();
 
-    let model: Vec<&str> = vec!["s_model"; combinations.len()];
-    let id: Vec<i32> = (1..=combinations.len() as i32).collect();
-    let age: Vec<i32> = combinations.iter().map(|(a, _, _)| *a).collect();
-    let term: Vec<i32> = combinations.iter().map(|(_, t, _)| *t).collect();
-    let gender: Vec<&str> = combinations.iter().map(|(_, _, g)| *g).collect();
-    // If more information on demographics is needed, it can be added here.
-    let policy_count: Vec<f64> = vec![1.0; combinations.len()];
-    let sum_insured: Vec<f64> = vec![1000.0; combinations.len()];
-
-    let df = df![
-        "model" => model,
-        "id" => id,
-        "age" => age,
-        "term" => term,
-        "gender" => gender,
-        "policy_count" => policy_count,
-        "sum_insured" => sum_insured,
-    ]?;
-
-    Ok(df)
+        let extra: Vec<Vec<&str>> = self
+            .extra_dimensions
+            .iter()
+            .map(|(_, values)| values.iter().map(|v| v.as_str()))
+            .multi_cartesian_product()
+            .collect();
+        let extra = if extra.is_empty() {
+            vec![vec![]]
+        } else {
+            extra
+        };
+
+        let combinations = iproduct!(base.iter(), extra.iter()).collect::<Vec<_>>();
+
+        let size = combinations.len();
+        let model: Vec<&str> = vec![self.model.as_str(); size];
+        let id: Vec<i32> = (1..=size as i32).collect();
+        let age: Vec<i32> = combinations.iter().map(|(b, _)| b.0).collect();
+        let term: Vec<i32> = combinations.iter().map(|(b, _)| b.1).collect();
+        let gender: Vec<&str> = combinations.iter().map(|(b, _)| b.2).collect();
+        let policy_count: Vec<f64> = vec![1.0; size];
+        let sum_insured: Vec<f64> = combinations.iter().map(|(b, _)| b.3).collect();
+
+        let mut df = df![
+            "model" => model,
+            "id" => id,
+            "entry_age" => age,
+            "term" => term,
+            "gender" => gender,
+            "policy_count" => policy_count,
+            "sum_insured" => sum_insured,
+        ]?;
+
+        for (idx, (name, _)) in self.extra_dimensions.iter().enumerate() {
+            let values: Vec<&str> = combinations.iter().map(|(_, e)| e[idx]).collect();
+            df.with_column(Series::new(name.into(), values))?;
+        }
+
+        Ok(df)
+    }
+
+    fn _validate(&self) -> PolarsResult<()> {
+        let empty_dimension = [
+            ("ages", self.ages.is_empty()),
+            ("terms", self.terms.is_empty()),
+            ("genders", self.genders.is_empty()),
+            ("sum_insureds", self.sum_insureds.is_empty()),
+        ]
+        .into_iter()
+        .chain(
+            self.extra_dimensions
+                .iter()
+                .map(|(name, values)| (name.as_str(), values.is_empty())),
+        )
+        .find(|(_, is_empty)| *is_empty);
+
+        if let Some((name, _)) = empty_dimension {
+            return Err(PolarsError::ComputeError(
+                format!("Pricing grid dimension '{name}' has no values").into(),
+            ));
+        }
+
+        if self.terms.iter().any(|&t| t <= 0) || self.ages.iter().any(|&a| a < 0) {
+            return Err(PolarsError::ComputeError(
+                "Pricing grid terms must be positive and ages non-negative".into(),
+            ));
+        }
+
+        // Each extra dimension adds a column of its own
+        for (idx, (name, _)) in self.extra_dimensions.iter().enumerate() {
+            let is_base = BASE_COLUMNS.contains(&name.as_str());
+            let is_repeated = self.extra_dimensions[..idx].iter().any(|(n, _)| n == name);
+            if is_base || is_repeated {
+                return Err(PolarsError::ComputeError(
+                    format!("Pricing grid dimension '{name}' is already a column of the grid")
+                        .into(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+//---------------------------------------------------------------------------------------------------------
+// PUBLIC
+//---------------------------------------------------------------------------------------------------------
+pub fn generate_s_model_points() -> PolarsResult<DataFrame> {
+    PricingGrid::default().generate()
+}
+
+// S model grid shared by the unit tests - both terms and genders with a single sum insured
+#[cfg(test)]
+pub fn test_pricing_grid(ages: &[i32]) -> PricingGrid {
+    PricingGrid::new("s_model")
+        .with_ages(ages)
+        .with_terms(&[10, 20])
+        .with_genders(&["M", "F"])
+        .with_sum_insureds(&[100_000.0])
+}
+
+//---------------------------------------------------------------------------------------------------------
+// UNIT TESTS
+//---------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fn_generate_s_model_points() {
+        let df = generate_s_model_points().unwrap();
+
+        println!("{df:?}");
+
+        assert_eq!(df.height(), 40 * 3 * 2);
+    }
+
+    #[test]
+    fn test_method_pricing_grid_generate() {
+        let grid = PricingGrid::new("s_model")
+            .with_age_range(20, 29)
+            .with_age_range(40, 44)
+            .with_terms(&[10, 20])
+            .with_genders(&["M", "F"])
+            .with_sum_insureds(&[100_000.0, 500_000.0])
+            .with_dimension("smoker", &["S", "NS"]);
+
+        let df = grid.generate().unwrap();
+
+        println!("{df:?}");
+
+        assert_eq!(df.height(), 15 * 2 * 2 * 2 * 2);
+        assert_eq!(df.height(), grid.model_points_count());
+        assert_eq!(df.column("id").unwrap().n_unique().unwrap(), df.height());
+        assert!(df.column("smoker").is_ok());
+    }
+
+    #[test]
+    fn test_method_pricing_grid_generate_base_column_dimension() {
+        let grid = PricingGrid::new("s_model")
+            .with_ages(&[30])
+            .with_terms(&[10])
+            .with_genders(&["M"])
+            .with_sum_insureds(&[100_000.0]);
+
+        for name in ["id", "term", "gender"] {
+            assert!(
+                grid.clone()
+                    .with_dimension(name, &["1", "2"])
+                    .generate()
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_method_pricing_grid_generate_repeated_dimension() {
+        let grid = PricingGrid::new("s_model")
+            .with_ages(&[30])
+            .with_terms(&[10])
+            .with_genders(&["M"])
+            .with_sum_insureds(&[100_000.0])
+            .with_dimension("smoker", &["S", "NS"])
+            .with_dimension("smoker", &["S", "NS"]);
+
+        assert!(grid.generate().is_err());
+    }
+
+    #[test]
+    fn test_method_pricing_grid_generate_empty_dimension() {
+        let grid = PricingGrid::new("s_model").with_age_range(20, 29);
+
+        assert!(grid.generate().is_err());
+    }
 }
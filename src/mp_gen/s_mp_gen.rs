@@ -1,5 +1,181 @@
 use super::*;
+use ndarray_rand::rand_distr::{StandardNormal, WeightedIndex};
 
+//---------------------------------------------------------------------------------------------------------
+// STRUCTS
+//---------------------------------------------------------------------------------------------------------
+/*
+Portfolio generator driven by distributions instead of uniform draws:
+- Entry age from an empirical histogram of (age_from, age_to, weight) bands
+- Term and gender from weighted mixes
+- Sum insured lognormal, rounded to a multiple of sum_insured_rounding
+- Age and log sum insured linked by a Gaussian copula with correlation age_sum_insured_corr
+*/
+#[derive(Clone, Debug)]
+pub struct PortfolioConfig {
+    pub model: String,
+    pub age_histogram: Vec<(i32, i32, f64)>, // Inclusive age bands with their weight
+    pub term_mix: Vec<(i32, f64)>,
+    pub gender_mix: Vec<(String, f64)>,
+    pub sum_insured_median: f64,
+    pub sum_insured_sigma: f64, // Standard deviation of log sum insured
+    pub sum_insured_rounding: f64,
+    pub age_sum_insured_corr: f64,
+}
+
+impl Default for PortfolioConfig {
+    fn default() -> Self {
+        Self {
+            model: "s_model".to_string(),
+            age_histogram: vec![
+                (20, 29, 0.25),
+                (30, 39, 0.35),
+                (40, 49, 0.25),
+                (50, 59, 0.15),
+            ],
+            term_mix: vec![(10, 0.3), (15, 0.3), (20, 0.4)],
+            gender_mix: vec![("M".to_string(), 0.5), ("F".to_string(), 0.5)],
+            sum_insured_median: 300_000.0,
+            sum_insured_sigma: 0.6,
+            sum_insured_rounding: 1000.0,
+            age_sum_insured_corr: 0.0,
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl PortfolioConfig {
+    pub fn generate(&self, mp_size: usize, seed: usize) -> PolarsResult<DataFrame> {
+        self._validate()?;
+
+        // Get seed for random number generation
+        let mut rng = StdRng::seed_from_u64(seed as u64);
+
+        // Correlated standard normals driving entry age and sum insured
+        let z_age = Array1::<f64>::random_using(mp_size, StandardNormal, &mut rng);
+        let z_noise = Array1::<f64>::random_using(mp_size, StandardNormal, &mut rng);
+        let rho = self.age_sum_insured_corr;
+        let z_sum_insured = &z_age * rho + &z_noise * (1.0 - rho * rho).sqrt();
+
+        // Entry age (Integer): Inverse of the empirical histogram applied to the uniform of z_age
+        let entry_age = z_age.mapv(|z| self._age_from_uniform(_std_normal_cdf(z)));
+
+        // Policy term (Integer) and gender (String): Weighted mixes
+        let term_idx = Array1::random_using(mp_size, _weighted_index(&self.term_mix)?, &mut rng);
+        let term: Vec<i32> = term_idx.iter().map(|&i| self.term_mix[i].0).collect();
+
+        let gender_idx =
+            Array1::random_using(mp_size, _weighted_index(&self.gender_mix)?, &mut rng);
+        let gender: Vec<&str> = gender_idx
+            .iter()
+            .map(|&i| self.gender_mix[i].0.as_str())
+            .collect();
+
+        // Policy count
+        let policy_count = Array1::<f64>::ones(mp_size);
+
+        // Sum insured (Float): Lognormal rounded to the nearest multiple of the rounding unit (at least one unit)
+        let rounding = self.sum_insured_rounding;
+        let sum_insured = z_sum_insured.mapv(|z| {
+            let x = self.sum_insured_median * (self.sum_insured_sigma * z).exp();
+            ((x / rounding).round() * rounding).max(rounding)
+        });
+
+        let model_points_df = df![
+            "model" => vec![self.model.as_str(); mp_size],
+            "id"  => (1..(mp_size+1) as i32).collect::<Vec<i32>>(),
+            "entry_age" => entry_age.to_vec(),
+            "gender" => gender,
+            "term" => term,
+            "policy_count" => policy_count.to_vec(),
+            "sum_insured" => sum_insured.to_vec(),
+        ]?;
+
+        Ok(model_points_df)
+    }
+
+    fn _age_from_uniform(&self, u: f64) -> i32 {
+        let total: f64 = self.age_histogram.iter().map(|(_, _, w)| w).sum();
+        let mut cumulative = 0.0;
+
+        for &(age_from, age_to, weight) in self.age_histogram.iter() {
+            let band_prob = weight / total;
+            if u < cumulative + band_prob && band_prob > 0.0 {
+                // Spread the remaining probability evenly over the ages in the band
+                let frac = (u - cumulative) / band_prob;
+                let age_count = (age_to - age_from + 1) as f64;
+                return age_from + ((frac * age_count) as i32).min(age_to - age_from);
+            }
+            cumulative += band_prob;
+        }
+
+        self.age_histogram
+            .last()
+            .map(|(_, age_to, _)| *age_to)
+            .unwrap_or(0)
+    }
+
+    fn _validate(&self) -> PolarsResult<()> {
+        if self.age_histogram.is_empty()
+            || self
+                .age_histogram
+                .iter()
+                .any(|(from, to, w)| from > to || *w < 0.0)
+            || self.age_histogram.iter().map(|(_, _, w)| w).sum::<f64>() <= 0.0
+        {
+            return Err(PolarsError::ComputeError(
+                "Age histogram must have valid bands and a positive total weight".into(),
+            ));
+        }
+
+        if !(-1.0..=1.0).contains(&self.age_sum_insured_corr) {
+            return Err(PolarsError::ComputeError(
+                "Age and sum insured correlation must be between -1 and 1".into(),
+            ));
+        }
+
+        if self.sum_insured_median <= 0.0
+            || self.sum_insured_sigma < 0.0
+            || self.sum_insured_rounding <= 0.0
+        {
+            return Err(PolarsError::ComputeError(
+                "Sum insured median and rounding must be positive and sigma non-negative".into(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+//---------------------------------------------------------------------------------------------------------
+// PRIVATE
+//---------------------------------------------------------------------------------------------------------
+fn _weighted_index<T>(mix: &[(T, f64)]) -> PolarsResult<WeightedIndex<f64>> {
+    WeightedIndex::new(mix.iter().map(|(_, w)| *w))
+        .map_err(|e| PolarsError::ComputeError(format!("Invalid weighted mix: {e}").into()))
+}
+
+// Standard normal CDF - Abramowitz and Stegun 7.1.26 approximation of erf (error < 1.5e-7)
+fn _std_normal_cdf(z: f64) -> f64 {
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    let erf = 1.0 - poly * (-x * x).exp();
+
+    if z >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+//---------------------------------------------------------------------------------------------------------
+// PUBLIC
+//---------------------------------------------------------------------------------------------------------
+
+#[allow(dead_code)]
 pub fn generate_s_model_points(mp_size: usize, seed: usize) -> PolarsResult<DataFrame> {
     // Get seed for random number generation
     let mut rng = StdRng::seed_from_u64(seed as u64);
@@ -22,10 +198,11 @@ pub fn generate_s_model_points(mp_size: usize, seed: usize) -> PolarsResult<Data
 
     // Sum insured (Float): Random values between 100,000 and 1,000,000 (multiple of 1000)
     let sum_insured = Array1::random_using(mp_size, Uniform::new(0.0f64, 1.0f64), &mut rng) // Random floats between 0 and 1
-        .mapv(|x| (((900_000.0 * x + 100_000.0) / 1000.0).round() * 1000.0));
+        .mapv(|x| ((900_000.0 * x + 100_000.0) / 1000.0).round() * 1000.0);
 
     // Create a DataFrame with the generated data
     let model_points_df = df![
+        "model" => vec!["s_model"; mp_size],
         "id"  => (1..(mp_size+1) as i32).collect::<Vec<i32>>(),
         "entry_age" => entry_age.to_vec(),
         "gender" => gender,
@@ -36,3 +213,64 @@ pub fn generate_s_model_points(mp_size: usize, seed: usize) -> PolarsResult<Data
 
     Ok(model_points_df)
 }
+
+//---------------------------------------------------------------------------------------------------------
+// UNIT TESTS
+//---------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_method_portfolio_config_generate_reproducible() {
+        let config = PortfolioConfig::default();
+        let df_1 = config.generate(1000, 7).unwrap();
+        let df_2 = config.generate(1000, 7).unwrap();
+
+        println!("{df_1:?}");
+
+        assert!(
+            df_1.equals(&df_2),
+            "Same seed should give the same portfolio"
+        );
+    }
+
+    #[test]
+    fn test_method_portfolio_config_generate_correlated() {
+        let config = PortfolioConfig {
+            age_sum_insured_corr: 0.8,
+            ..Default::default()
+        };
+        let df = config.generate(5000, 7).unwrap();
+
+        let age: Vec<f64> = df
+            .column("entry_age")
+            .unwrap()
+            .i32()
+            .unwrap()
+            .into_no_null_iter()
+            .map(|a| a as f64)
+            .collect();
+        let log_si: Vec<f64> = df
+            .column("sum_insured")
+            .unwrap()
+            .f64()
+            .unwrap()
+            .into_no_null_iter()
+            .map(f64::ln)
+            .collect();
+
+        let (age, log_si) = (Array1::from(age), Array1::from(log_si));
+        let cov = ((&age - age.mean().unwrap()) * (&log_si - log_si.mean().unwrap()))
+            .mean()
+            .unwrap();
+        let corr = cov / (age.std(0.0) * log_si.std(0.0));
+
+        assert!(
+            corr > 0.5,
+            "Older ages should have larger sums insured: {corr}"
+        );
+
+        assert!(age.iter().all(|&a| (20.0..=59.0).contains(&a)));
+    }
+}
@@ -0,0 +1,311 @@
+use polars::prelude::*;
+use std::collections::HashMap;
+
+//---------------------------------------------------------------------------------------------------------
+// STRUCTS
+//---------------------------------------------------------------------------------------------------------
+#[allow(dead_code)]
+#[derive(Clone, Debug)]
+pub struct ModelPointProfile {
+    pub describe_df: DataFrame, // Summary statistics of the numeric columns
+    pub counts_df: DataFrame, // Model points, policies and sum insured by model, gender, term and age band
+    pub issues_df: DataFrame, // One row per flagged model point and issue
+    pub summary: String,      // Text summary to print before a run
+}
+
+//---------------------------------------------------------------------------------------------------------
+// PUBLIC
+//---------------------------------------------------------------------------------------------------------
+// Profile a model point file against the mortality table that will be used to project it
+#[allow(dead_code)]
+pub fn profile_model_points(
+    model_points_df: &DataFrame,
+    mort_df: &DataFrame,
+    age_band_width: i32,
+) -> PolarsResult<ModelPointProfile> {
+    if age_band_width < 1 {
+        return Err(PolarsError::ComputeError(
+            "Age band width must be at least 1 year".into(),
+        ));
+    }
+
+    let df = model_points_df
+        .clone()
+        .lazy()
+        .with_columns(vec![
+            col("id").cast(DataType::Int32),
+            col("entry_age").cast(DataType::Int32),
+            col("term").cast(DataType::Int32),
+            col("policy_count").cast(DataType::Float64),
+            col("sum_insured").cast(DataType::Float64),
+        ])
+        .with_columns(vec![
+            ((col("entry_age") / lit(age_band_width)) * lit(age_band_width)).alias("age_band"),
+            (col("sum_insured") * col("policy_count")).alias("total_sum_insured"),
+        ])
+        .collect()?;
+
+    let describe_df = _describe(&df, &["entry_age", "term", "policy_count", "sum_insured"])?;
+    let counts_df = _counts(&df, age_band_width)?;
+    let issues_df = _issues(&df, mort_df)?;
+    let summary = _summary(&df, &counts_df, &issues_df)?;
+
+    let result = ModelPointProfile {
+        describe_df,
+        counts_df,
+        issues_df,
+        summary,
+    };
+
+    Ok(result)
+}
+
+//---------------------------------------------------------------------------------------------------------
+// PRIVATE
+//---------------------------------------------------------------------------------------------------------
+// Same layout as the describe output in Python polars: one row per statistic, one column per field
+fn _describe(df: &DataFrame, col_names: &[&str]) -> PolarsResult<DataFrame> {
+    type StatFn = fn(Expr) -> Expr;
+
+    let stats: [(&str, StatFn); 7] = [
+        ("count", |e| e.count().cast(DataType::Float64)),
+        ("null_count", |e| e.null_count().cast(DataType::Float64)),
+        ("mean", |e| e.mean()),
+        ("std", |e| e.std(1)),
+        ("min", |e| e.min()),
+        ("median", |e| e.median()),
+        ("max", |e| e.max()),
+    ];
+
+    let all_lfs = stats
+        .iter()
+        .map(|(name, stat)| {
+            let mut exprs = vec![lit(*name).alias("statistic")];
+            exprs.extend(
+                col_names
+                    .iter()
+                    .map(|c| stat(col(*c).cast(DataType::Float64)).alias(*c)),
+            );
+            df.clone().lazy().select(exprs)
+        })
+        .collect::<Vec<LazyFrame>>();
+
+    concat(all_lfs, Default::default())?.collect()
+}
+
+fn _counts(df: &DataFrame, age_band_width: i32) -> PolarsResult<DataFrame> {
+    let mut counts_df = DataFrame::empty();
+
+    for dimension in ["model", "gender", "term", "age_band"] {
+        let mut dimension_df = df
+            .clone()
+            .lazy()
+            .group_by([col(dimension)])
+            .agg([
+                col("id")
+                    .count()
+                    .cast(DataType::Int32)
+                    .alias("model_points"),
+                col("policy_count").sum(),
+                col("total_sum_insured").sum().alias("sum_insured"),
+            ])
+            .sort([dimension], Default::default())
+            .collect()?;
+
+        // Label each group as text so all dimensions can be stacked together
+        let values: Vec<String> = match dimension {
+            "age_band" => dimension_df
+                .column(dimension)?
+                .i32()?
+                .into_iter()
+                .map(|a| {
+                    let a = a.unwrap_or_default();
+                    format!("{a}-{}", a + age_band_width - 1)
+                })
+                .collect(),
+            _ => dimension_df
+                .column(dimension)?
+                .cast(&DataType::String)?
+                .str()?
+                .into_iter()
+                .map(|v| v.unwrap_or_default().to_string())
+                .collect(),
+        };
+
+        dimension_df.with_column(Series::new(dimension.into(), values))?;
+
+        let dimension_df = dimension_df
+            .lazy()
+            .select([
+                lit(dimension).alias("dimension"),
+                col(dimension).alias("value"),
+                col("model_points"),
+                col("policy_count"),
+                col("sum_insured"),
+            ])
+            .collect()?;
+
+        counts_df.vstack_mut(&dimension_df)?;
+    }
+
+    Ok(counts_df)
+}
+
+fn _issues(df: &DataFrame, mort_df: &DataFrame) -> PolarsResult<DataFrame> {
+    let id = df.column("id")?.i32()?;
+    let entry_age = df.column("entry_age")?.i32()?;
+    let term = df.column("term")?.i32()?;
+    let policy_count = df.column("policy_count")?.f64()?;
+    let sum_insured = df.column("sum_insured")?.f64()?;
+
+    // Highest age covered by the mortality table
+    let max_age = mort_df.column("age")?.cast(&DataType::Int32)?.i32()?.max();
+
+    // Sum insured outliers are more than 3 standard deviations away from the mean
+    let si_mean = sum_insured.mean().unwrap_or(0.0);
+    let si_std = sum_insured.std(1).unwrap_or(0.0);
+
+    let mut id_counts: HashMap<i32, usize> = HashMap::new();
+    for i in id.into_iter().flatten() {
+        *id_counts.entry(i).or_insert(0) += 1;
+    }
+
+    let mut issue_ids: Vec<Option<i32>> = Vec::new();
+    let mut issues: Vec<String> = Vec::new();
+
+    for row in 0..df.height() {
+        let row_id = id.get(row);
+        let mut flag = |issue: String| {
+            issue_ids.push(row_id);
+            issues.push(issue);
+        };
+
+        if row_id.is_none_or(|i| id_counts.get(&i).copied().unwrap_or(0) > 1) {
+            flag("duplicate_id".to_string());
+        }
+
+        let (age, t) = (entry_age.get(row), term.get(row));
+        let (count, si) = (policy_count.get(row), sum_insured.get(row));
+
+        if age.is_none_or(|a| a < 0)
+            || t.is_none_or(|t| t <= 0)
+            || count.is_none_or(|c| c <= 0.0)
+            || si.is_none_or(|s| s <= 0.0)
+        {
+            flag("invalid_value".to_string());
+        }
+
+        if let Some(s) = si
+            && si_std > 0.0
+            && ((s - si_mean) / si_std).abs() > 3.0
+        {
+            flag("sum_insured_outlier".to_string());
+        }
+
+        if let (Some(a), Some(t), Some(max_age)) = (age, t, max_age)
+            && a + t > max_age
+        {
+            flag("term_beyond_mortality_table".to_string());
+        }
+    }
+
+    df!(
+        "id" => issue_ids,
+        "issue" => issues,
+    )
+}
+
+fn _summary(df: &DataFrame, counts_df: &DataFrame, issues_df: &DataFrame) -> PolarsResult<String> {
+    let policy_count = df.column("policy_count")?.f64()?.sum().unwrap_or(0.0);
+    let sum_insured = df.column("total_sum_insured")?.f64()?.sum().unwrap_or(0.0);
+
+    let mut lines = vec![
+        format!("Model points: {}", df.height()),
+        format!("Policy count: {policy_count:.2}"),
+        format!("Sum insured: {sum_insured:.2}"),
+    ];
+
+    // Number of distinct values in each dimension
+    let dimension = counts_df.column("dimension")?.str()?;
+    for name in ["model", "gender", "term", "age_band"] {
+        let n = dimension.into_iter().filter(|d| *d == Some(name)).count();
+        lines.push(format!("Distinct {name}: {n}"));
+    }
+
+    // Number of flagged model points by issue
+    let mut issue_counts: Vec<(String, usize)> = Vec::new();
+    for issue in issues_df.column("issue")?.str()?.into_iter().flatten() {
+        match issue_counts.iter_mut().find(|(name, _)| name == issue) {
+            Some((_, n)) => *n += 1,
+            None => issue_counts.push((issue.to_string(), 1)),
+        }
+    }
+
+    if issue_counts.is_empty() {
+        lines.push("No data quality issues found".to_string());
+    } else {
+        for (issue, n) in issue_counts.iter() {
+            lines.push(format!("Issue {issue}: {n}"));
+        }
+    }
+
+    Ok(lines.join("\n"))
+}
+
+//---------------------------------------------------------------------------------------------------------
+// UNIT TESTS
+//---------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assumptions::assumption_scenario::AssumptionScenario;
+    use crate::mp_gen::pricing_mp_gen::PricingGrid;
+
+    #[test]
+    fn test_fn_profile_model_points() {
+        let mut df = PricingGrid::default().generate().unwrap();
+        let scenario = AssumptionScenario::new_by_name("pricing").unwrap();
+
+        // Introduce a duplicate id and a term running beyond the mortality table
+        let mut id: Vec<i32> = df
+            .column("id")
+            .unwrap()
+            .i32()
+            .unwrap()
+            .into_no_null_iter()
+            .collect();
+        id[1] = id[0];
+        let mut term: Vec<i32> = df
+            .column("term")
+            .unwrap()
+            .i32()
+            .unwrap()
+            .into_no_null_iter()
+            .collect();
+        term[2] = 150;
+        df.with_column(Series::new("id".into(), id)).unwrap();
+        df.with_column(Series::new("term".into(), term)).unwrap();
+
+        let profile = profile_model_points(&df, &scenario.mort, 10).unwrap();
+
+        println!("{:?}", profile.describe_df);
+        println!("{:?}", profile.counts_df);
+        println!("{}", profile.summary);
+
+        assert_eq!(profile.describe_df.height(), 7);
+
+        let issues = profile.issues_df.column("issue").unwrap().str().unwrap();
+        assert_eq!(
+            issues
+                .into_iter()
+                .filter(|i| *i == Some("duplicate_id"))
+                .count(),
+            2
+        );
+        assert!(
+            issues
+                .into_iter()
+                .any(|i| i == Some("term_beyond_mortality_table"))
+        );
+    }
+}
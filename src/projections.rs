@@ -1,4 +1,8 @@
+pub mod annual_aggregation;
+pub mod capital;
 mod helpers;
+pub mod portfolio_aggregation;
+pub mod profit_test;
 pub mod projection_mp;
 pub mod projection_multi_runs;
 pub mod projection_single_run;
@@ -0,0 +1,295 @@
+use chrono::{Datelike, NaiveDate};
+use polars::prelude::*;
+
+//---------------------------------------------------------------------------------------------------------
+// STRUCTS
+//---------------------------------------------------------------------------------------------------------
+// Year the monthly projection is aggregated by
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum YearBasis {
+    PolicyYear,   // From the `duration` column
+    CalendarYear, // From the `date` column
+}
+
+impl YearBasis {
+    pub fn column_name(&self) -> &'static str {
+        match self {
+            YearBasis::PolicyYear => "policy_year",
+            YearBasis::CalendarYear => "calendar_year",
+        }
+    }
+}
+
+// Columns summed over the year
+pub const FLOW_COLUMNS: [&str; 19] = [
+    "pols_maturity",
+    "pols_death",
+    "pols_lapse",
+    "premiums",
+    "claims",
+    "expenses",
+    "initial_commissions",
+    "renewal_commissions",
+    "clawback_commissions",
+    "commissions",
+    "ceded_claims",
+    "reinsurance_premiums",
+    "reinsurance_commissions",
+    "premium_tax",
+    "taxable_profit",
+    "tax",
+    "net_cf",
+    "investment_income",
+    "statutory_profit",
+];
+
+// Columns taken at the start of the year
+pub const STOCK_COLUMNS: [&str; 4] = [
+    "pols_if",
+    "net_premium_reserve",
+    "gross_premium_reserve",
+    "assets_bop",
+];
+
+//---------------------------------------------------------------------------------------------------------
+// PUBLIC
+//---------------------------------------------------------------------------------------------------------
+// Annual figures by year and model point attributes: flows are summed, stocks are the value of each
+// model point in the first month of the year, summed over the model points of a group
+pub fn annual_df(
+    projected_df: &DataFrame,
+    model_points_df: &DataFrame,
+    basis: YearBasis,
+    group_columns: &[&str],
+) -> PolarsResult<DataFrame> {
+    let available: Vec<String> = projected_df
+        .get_column_names()
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+    let has = |name: &str| available.iter().any(|a| a == name);
+    let flows: Vec<&str> = FLOW_COLUMNS.into_iter().filter(|c| has(c)).collect();
+    let stocks: Vec<&str> = STOCK_COLUMNS.into_iter().filter(|c| has(c)).collect();
+
+    let year = basis.column_name();
+    let mut df = projected_df.clone();
+    df.with_column(_year(projected_df, basis)?)?;
+
+    // Model point attributes defining the groups, unless projected already (eg: term)
+    let mut attribute_exprs = vec![col("id").cast(DataType::Int32)];
+    attribute_exprs.extend(group_columns.iter().filter(|c| !has(c)).map(|c| col(*c)));
+    let attributes_lf = model_points_df.clone().lazy().select(attribute_exprs);
+
+    // Each model point first, so stocks are taken from its own first month in the year
+    let mut mp_keys = vec![col("id"), col(year)];
+    mp_keys.extend(group_columns.iter().map(|c| col(*c)));
+    let mut mp_aggs: Vec<Expr> = flows.iter().map(|c| col(*c).sum()).collect();
+    mp_aggs.extend(stocks.iter().map(|c| col(*c).first()));
+
+    let mut keys: Vec<Expr> = group_columns.iter().map(|c| col(*c)).collect();
+    keys.push(col(year));
+    let aggs: Vec<Expr> = flows
+        .iter()
+        .chain(stocks.iter())
+        .map(|c| col(*c).sum())
+        .collect();
+
+    let mut sort_cols: Vec<String> = group_columns.iter().map(|c| c.to_string()).collect();
+    sort_cols.push(year.to_string());
+
+    df.lazy()
+        .join(
+            attributes_lf,
+            [col("id")],
+            [col("id")],
+            JoinArgs::new(JoinType::Left),
+        )
+        .group_by(mp_keys)
+        .agg(mp_aggs)
+        .group_by(keys)
+        .agg(aggs)
+        .sort(sort_cols, Default::default())
+        .collect()
+}
+
+//---------------------------------------------------------------------------------------------------------
+// PRIVATE
+//---------------------------------------------------------------------------------------------------------
+fn _year(projected_df: &DataFrame, basis: YearBasis) -> PolarsResult<Series> {
+    let required = match basis {
+        YearBasis::PolicyYear => "duration",
+        YearBasis::CalendarYear => "date",
+    };
+    let column = projected_df.column(required).map_err(|_| {
+        PolarsError::ColumnNotFound(
+            format!(
+                "Column '{required}' is needed to aggregate by {}",
+                basis.column_name()
+            )
+            .into(),
+        )
+    })?;
+
+    let years: Vec<Option<i32>> = match basis {
+        YearBasis::PolicyYear => column
+            .cast(&DataType::Int32)?
+            .i32()?
+            .into_iter()
+            .map(|d| d.map(|d| d + 1))
+            .collect(),
+        // Polars dates are days since 1970-01-01
+        YearBasis::CalendarYear => column
+            .cast(&DataType::Int32)?
+            .i32()?
+            .into_iter()
+            .map(|days| {
+                days.and_then(|days| NaiveDate::from_num_days_from_ce_opt(days + 719_163))
+                    .map(|date| date.year())
+            })
+            .collect(),
+    };
+
+    Ok(Series::new(
+        basis.column_name().into(),
+        __fold_terminal_month(projected_df, years)?,
+    ))
+}
+
+// The projection of each model point ends with the month after its term, which only closes the last
+// month - it takes the year of that month rather than starting a year of its own
+fn __fold_terminal_month(
+    projected_df: &DataFrame,
+    mut years: Vec<Option<i32>>,
+) -> PolarsResult<Vec<Option<i32>>> {
+    let id = projected_df.column("id")?.cast(&DataType::Int32)?;
+    let id: Vec<Option<i32>> = id.i32()?.into_iter().collect();
+
+    for i in 1..years.len() {
+        let is_last = i + 1 == years.len() || id[i + 1] != id[i];
+        if is_last && id[i - 1] == id[i] {
+            years[i] = years[i - 1];
+        }
+    }
+
+    Ok(years)
+}
+
+//---------------------------------------------------------------------------------------------------------
+// UNIT TESTS
+//---------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assumptions::assumption_scenario::AssumptionScenario;
+    use crate::mp_gen::pricing_mp_gen::test_pricing_grid;
+    use crate::projections::projection_single_run::SingleRunSetup;
+
+    #[test]
+    fn test_fn_annual_df() {
+        let model_points_df = test_pricing_grid(&[30, 50]).generate().unwrap();
+        let result = SingleRunSetup::new(
+            "Annual",
+            model_points_df.clone(),
+            AssumptionScenario::new_by_name("pricing").unwrap(),
+        )
+        .projection_run()
+        .unwrap();
+
+        let annual_df = annual_df(
+            &result.projected_df,
+            &model_points_df,
+            YearBasis::PolicyYear,
+            &["term"],
+        )
+        .unwrap();
+
+        println!("{annual_df:?}");
+
+        // 10 and 20 policy years - the maturity month closes the last year
+        assert_eq!(annual_df.height(), 10 + 20);
+
+        let value = |term: i32, year: i32, name: &str| -> f64 {
+            let row = annual_df
+                .clone()
+                .lazy()
+                .filter(
+                    col("term")
+                        .eq(lit(term))
+                        .and(col("policy_year").eq(lit(year))),
+                )
+                .collect()
+                .unwrap();
+            row.column(name).unwrap().f64().unwrap().get(0).unwrap()
+        };
+        let monthly_total = |term: i32, months: std::ops::Range<f64>, name: &str| -> f64 {
+            let df = result
+                .projected_df
+                .clone()
+                .lazy()
+                .filter(
+                    col("term")
+                        .eq(lit(term))
+                        .and(col("t").gt_eq(lit(months.start)))
+                        .and(col("t").lt(lit(months.end))),
+                )
+                .collect()
+                .unwrap();
+            df.column(name).unwrap().f64().unwrap().sum().unwrap()
+        };
+
+        // Flows are summed over the year, stocks are taken at its start
+        let premiums = monthly_total(10, 12.0..24.0, "premiums");
+        assert!((value(10, 2, "premiums") - premiums).abs() < 1e-6);
+        let pols_if = monthly_total(10, 12.0..13.0, "pols_if");
+        assert!((value(10, 2, "pols_if") - pols_if).abs() < 1e-9);
+        let net_cf = monthly_total(10, 108.0..121.0, "net_cf");
+        assert!((value(10, 10, "net_cf") - net_cf).abs() < 1e-6);
+
+        // Calendar year needs a date
+        assert!(
+            super::annual_df(
+                &result.projected_df,
+                &model_points_df,
+                YearBasis::CalendarYear,
+                &[]
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_fn_annual_df_calendar_year() {
+        // Policy issued in November: two months in the first calendar year
+        let date: Vec<NaiveDate> = (11..=12)
+            .map(|m| NaiveDate::from_ymd_opt(2024, m, 1).unwrap())
+            .chain((1..=3).map(|m| NaiveDate::from_ymd_opt(2025, m, 1).unwrap()))
+            .collect();
+        let projected_df = DataFrame::new(vec![
+            Column::new("id".into(), [1; 5]),
+            Column::new("date".into(), date),
+            Column::new("premiums".into(), [10.0, 10.0, 9.0, 9.0, 9.0]),
+            Column::new("pols_if".into(), [1.0, 0.99, 0.98, 0.97, 0.96]),
+        ])
+        .unwrap();
+        let model_points_df = df!["id" => [1]].unwrap();
+
+        let annual_df = annual_df(
+            &projected_df,
+            &model_points_df,
+            YearBasis::CalendarYear,
+            &[],
+        )
+        .unwrap();
+
+        println!("{annual_df:?}");
+
+        let expected = df![
+            "calendar_year" => [2024, 2025],
+            "premiums" => [20.0, 27.0],
+            "pols_if" => [1.0, 0.98],
+        ]
+        .unwrap();
+        assert!(annual_df.equals(&expected));
+    }
+}
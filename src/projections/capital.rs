@@ -0,0 +1,369 @@
+use crate::assumptions::assumption_scenario::AssumptionScenario;
+use crate::projections::helpers::f64_values;
+use crate::projections::projection_single_run::{OutputProfile, SingleRunSetup};
+use polars::prelude::*;
+
+//---------------------------------------------------------------------------------------------------------
+// STRUCTS
+//---------------------------------------------------------------------------------------------------------
+// Prescribed shock to the assumptions of a run
+#[allow(dead_code)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shock {
+    MortalityUp(f64),                           // Relative increase in mortality rates
+    LapseUp(f64),                               // Relative increase in lapse rates
+    LapseDown(f64),                             // Relative decrease in lapse rates
+    MassLapse(f64),                             // Share of policies lapsing at the valuation date
+    ExpenseUp { expense: f64, inflation: f64 }, // Relative increase in expenses, plus inflation
+    InterestUp(f64),                            // Absolute increase in spot rates
+    InterestDown(f64),                          // Absolute decrease in spot rates
+}
+
+impl Shock {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Shock::MortalityUp(_) => "mortality_up",
+            Shock::LapseUp(_) => "lapse_up",
+            Shock::LapseDown(_) => "lapse_down",
+            Shock::MassLapse(_) => "mass_lapse",
+            Shock::ExpenseUp { .. } => "expense_up",
+            Shock::InterestUp(_) => "interest_up",
+            Shock::InterestDown(_) => "interest_down",
+        }
+    }
+
+    // Shocked assumptions - premiums stay on the pricing basis of the unshocked run
+    pub fn apply(&self, assumptions: &AssumptionScenario) -> PolarsResult<AssumptionScenario> {
+        let mut shocked = assumptions.clone();
+        if shocked.pricing_basis.is_none() {
+            shocked.pricing_basis = Some(Box::new(assumptions.clone()));
+        }
+
+        match *self {
+            Shock::MortalityUp(s) => {
+                shocked.mort = _shock_table(&shocked.mort, |q| (q * (1.0 + s)).min(1.0))?
+            }
+            Shock::LapseUp(s) => {
+                shocked.lapse = _shock_table(&shocked.lapse, |q| (q * (1.0 + s)).min(1.0))?
+            }
+            Shock::LapseDown(s) => shocked.lapse = _shock_table(&shocked.lapse, |q| q * (1.0 - s))?,
+            Shock::MassLapse(s) => shocked.mass_lapse = s,
+            Shock::ExpenseUp { expense, inflation } => {
+                shocked.acq = _shock_table(&shocked.acq, |e| e * (1.0 + expense))?;
+                shocked.mtn = _shock_table(&shocked.mtn, |e| e * (1.0 + expense))?;
+                shocked.inf = _shock_table(&shocked.inf, |r| r + inflation)?;
+            }
+            Shock::InterestUp(s) => shocked.spot = _shock_table(&shocked.spot, |r| r + s)?,
+            Shock::InterestDown(s) => shocked.spot = _shock_table(&shocked.spot, |r| r - s)?,
+        }
+
+        Ok(shocked)
+    }
+}
+
+// Standard formula style capital: each risk needs the worst increase in best-estimate liability (BEL)
+// over its shocks, and the risks are aggregated with a correlation matrix
+#[derive(Clone, Debug)]
+pub struct CapitalCalculation {
+    pub risks: Vec<(String, Vec<Shock>)>,
+    pub correlation: Vec<Vec<f64>>, // Between the risks, in the same order
+    pub group_columns: Vec<String>, // Model point attributes defining a group
+}
+
+impl Default for CapitalCalculation {
+    fn default() -> Self {
+        Self {
+            risks: vec![
+                ("mortality".to_string(), vec![Shock::MortalityUp(0.15)]),
+                (
+                    "lapse".to_string(),
+                    vec![
+                        Shock::LapseUp(0.5),
+                        Shock::LapseDown(0.5),
+                        Shock::MassLapse(0.4),
+                    ],
+                ),
+                (
+                    "expense".to_string(),
+                    vec![Shock::ExpenseUp {
+                        expense: 0.1,
+                        inflation: 0.01,
+                    }],
+                ),
+                (
+                    "interest".to_string(),
+                    vec![Shock::InterestUp(0.01), Shock::InterestDown(0.01)],
+                ),
+            ],
+            correlation: vec![
+                vec![1.0, 0.0, 0.25, 0.25],
+                vec![0.0, 1.0, 0.5, 0.25],
+                vec![0.25, 0.5, 1.0, 0.25],
+                vec![0.25, 0.25, 0.25, 1.0],
+            ],
+            group_columns: vec!["model".to_string()],
+        }
+    }
+}
+
+#[allow(dead_code)]
+impl CapitalCalculation {
+    pub fn with_risks(
+        mut self,
+        risks: Vec<(&str, Vec<Shock>)>,
+        correlation: Vec<Vec<f64>>,
+    ) -> Self {
+        self.risks = risks
+            .into_iter()
+            .map(|(name, shocks)| (name.to_string(), shocks))
+            .collect();
+        self.correlation = correlation;
+        self
+    }
+
+    pub fn with_group_columns(mut self, group_columns: &[&str]) -> Self {
+        self.group_columns = group_columns.iter().map(|c| c.to_string()).collect();
+        self
+    }
+
+    // One row per group: BEL, change in BEL under each shock, capital by risk and in total
+    pub fn run(&self, setup: &SingleRunSetup) -> PolarsResult<DataFrame> {
+        self._validate()?;
+
+        let shocks: Vec<&Shock> = self.risks.iter().flat_map(|(_, s)| s.iter()).collect();
+
+        // BEL of each model point under the base and shocked assumptions
+        let mut bel_lf = _bel_by_id(setup, "bel")?.lazy();
+        for shock in shocks.iter() {
+            let shocked_setup = SingleRunSetup {
+                assumption_scenario: shock.apply(&setup.assumption_scenario)?,
+                ..setup.clone()
+            };
+            bel_lf = bel_lf.join(
+                _bel_by_id(&shocked_setup, shock.name())?.lazy(),
+                [col("id")],
+                [col("id")],
+                JoinArgs::new(JoinType::Left),
+            );
+        }
+
+        let mut group_exprs = vec![col("id").cast(DataType::Int32)];
+        group_exprs.extend(self.group_columns.iter().map(|c| col(c.as_str())));
+        let groups_lf = setup.model_points_df.clone().lazy().select(group_exprs);
+
+        let mut sum_exprs = vec![col("bel").sum()];
+        sum_exprs.extend(shocks.iter().map(|s| col(s.name()).sum()));
+
+        let group_df = bel_lf
+            .join(
+                groups_lf,
+                [col("id")],
+                [col("id")],
+                JoinArgs::new(JoinType::Left),
+            )
+            .group_by(
+                self.group_columns
+                    .iter()
+                    .map(|c| col(c.as_str()))
+                    .collect::<Vec<_>>(),
+            )
+            .agg(sum_exprs)
+            .sort(self.group_columns.clone(), Default::default())
+            .collect()?;
+
+        // Change in BEL under each shock
+        let bel = f64_values(&group_df, "bel")?;
+        let mut columns = Vec::new();
+        let mut deltas = Vec::new();
+        for shock in shocks.iter() {
+            let delta: Vec<f64> = f64_values(&group_df, shock.name())?
+                .iter()
+                .zip(bel.iter())
+                .map(|(shocked, base)| shocked - base)
+                .collect();
+            columns.push(Column::new(
+                format!("delta_{}", shock.name()).into(),
+                &delta,
+            ));
+            deltas.push(delta);
+        }
+
+        // Capital of each risk is the worst change in BEL, nil if every shock releases reserves
+        let mut risk_capitals = Vec::new();
+        let mut offset = 0;
+        for (name, risk_shocks) in self.risks.iter() {
+            let capital: Vec<f64> = (0..group_df.height())
+                .map(|g| {
+                    deltas[offset..offset + risk_shocks.len()]
+                        .iter()
+                        .fold(0.0, |worst: f64, delta| worst.max(delta[g]))
+                })
+                .collect();
+            offset += risk_shocks.len();
+            columns.push(Column::new(format!("{name}_capital").into(), &capital));
+            risk_capitals.push(capital);
+        }
+
+        let capital: Vec<f64> = (0..group_df.height())
+            .map(|g| {
+                let risk_capital: Vec<f64> = risk_capitals.iter().map(|c| c[g]).collect();
+                _aggregate(&risk_capital, &self.correlation)
+            })
+            .collect();
+        columns.push(Column::new("capital".into(), capital));
+
+        let mut keys: Vec<&str> = self.group_columns.iter().map(|c| c.as_str()).collect();
+        keys.push("bel");
+        group_df.select(keys)?.hstack(&columns)
+    }
+
+    fn _validate(&self) -> PolarsResult<()> {
+        let n = self.risks.len();
+        if self.correlation.len() != n || self.correlation.iter().any(|row| row.len() != n) {
+            return Err(PolarsError::ComputeError(
+                format!("Correlation matrix must be {n} x {n} for {n} risks").into(),
+            ));
+        }
+
+        let mut names: Vec<&str> = self
+            .risks
+            .iter()
+            .flat_map(|(_, shocks)| shocks.iter().map(|s| s.name()))
+            .collect();
+        let count = names.len();
+        names.sort();
+        names.dedup();
+        if names.len() != count {
+            return Err(PolarsError::ComputeError(
+                "Each shock can only be used once".into(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+//---------------------------------------------------------------------------------------------------------
+// PRIVATE
+//---------------------------------------------------------------------------------------------------------
+// Apply the shock to every rate column of an assumption table - the first column is the key
+fn _shock_table(df: &DataFrame, shock: impl Fn(f64) -> f64) -> PolarsResult<DataFrame> {
+    let mut shocked_df = df.clone();
+    let names: Vec<String> = df
+        .get_column_names()
+        .iter()
+        .skip(1)
+        .map(|name| name.to_string())
+        .collect();
+
+    for name in names {
+        let shocked = df
+            .column(&name)?
+            .cast(&DataType::Float64)?
+            .f64()?
+            .apply_values(&shock);
+        shocked_df.with_column(shocked.into_series().with_name(name.into()))?;
+    }
+
+    Ok(shocked_df)
+}
+
+// Gross premium reserve at t = 0 of each model point - only the columns needed are kept
+fn _bel_by_id(setup: &SingleRunSetup, name: &str) -> PolarsResult<DataFrame> {
+    let columns = ["id", "t", "gross_premium_reserve"].map(|c| c.to_string());
+
+    setup
+        .clone()
+        .with_output_profile(OutputProfile::Columns(columns.to_vec()))
+        .projection_run()?
+        .projected_df
+        .lazy()
+        .filter(col("t").eq(lit(0.0)))
+        .select([col("id"), col("gross_premium_reserve").alias(name)])
+        .collect()
+}
+
+// Square root of c' R c
+fn _aggregate(capital: &[f64], correlation: &[Vec<f64>]) -> f64 {
+    let mut total = 0.0;
+    for (i, row) in correlation.iter().enumerate() {
+        for (j, rho) in row.iter().enumerate() {
+            total += rho * capital[i] * capital[j];
+        }
+    }
+    total.max(0.0).sqrt()
+}
+
+//---------------------------------------------------------------------------------------------------------
+// UNIT TESTS
+//---------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp_gen::pricing_mp_gen::test_pricing_grid;
+
+    #[test]
+    fn test_method_capital_calculation_run() {
+        let model_points_df = test_pricing_grid(&[30, 50]).generate().unwrap();
+        let setup = SingleRunSetup::new(
+            "Capital",
+            model_points_df,
+            AssumptionScenario::new_by_name("pricing").unwrap(),
+        );
+
+        let capital_df = CapitalCalculation::default()
+            .with_group_columns(&["term"])
+            .run(&setup)
+            .unwrap();
+
+        println!("{capital_df:?}");
+
+        assert_eq!(capital_df.height(), 2);
+
+        // Higher mortality increases the liability of term assurance
+        let mortality = f64_values(&capital_df, "mortality_capital").unwrap();
+        let delta = f64_values(&capital_df, "delta_mortality_up").unwrap();
+        assert!(delta.iter().all(|&d| d > 0.0));
+        assert_eq!(mortality, delta);
+
+        // Diversified capital lies between the largest risk and the sum of the risks
+        let risks = ["mortality", "lapse", "expense", "interest"];
+        let capital = f64_values(&capital_df, "capital").unwrap();
+        for (g, &capital) in capital.iter().enumerate() {
+            let risk_capital: Vec<f64> = risks
+                .iter()
+                .map(|r| f64_values(&capital_df, &format!("{r}_capital")).unwrap()[g])
+                .collect();
+            let largest = risk_capital.iter().cloned().fold(0.0, f64::max);
+            assert!(capital >= largest - 1e-9);
+            assert!(capital <= risk_capital.iter().sum::<f64>() + 1e-9);
+        }
+
+        // Mismatched correlation matrix
+        let calculation =
+            CapitalCalculation::default().with_risks(vec![("mortality", vec![])], vec![]);
+        assert!(calculation.run(&setup).is_err());
+    }
+
+    #[test]
+    fn test_method_shock_apply() {
+        let assumptions = AssumptionScenario::new_by_name("pricing").unwrap();
+
+        let shocked = Shock::InterestUp(0.01).apply(&assumptions).unwrap();
+        let spot = f64_values(&assumptions.spot, "spot_rate").unwrap();
+        let shocked_spot = f64_values(&shocked.spot, "spot_rate").unwrap();
+        for (r, shocked_r) in spot.iter().zip(shocked_spot.iter()) {
+            assert!((shocked_r - r - 0.01).abs() < 1e-12);
+        }
+
+        // Premiums stay on the unshocked basis
+        assert!(shocked.pricing_basis.is_some());
+        assert_eq!(
+            Shock::MassLapse(0.4)
+                .apply(&assumptions)
+                .unwrap()
+                .mass_lapse,
+            0.4
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use polars::prelude::*;
 use std::fs::create_dir_all;
 use std::path::Path;
 
@@ -6,16 +7,25 @@ pub fn create_folder(path: &Path) {
 
     // If the path is a file, ensure the parent directory exists
     if is_file {
-        if let Some(parent) = path.parent() {
-            if !parent.exists() {
-                if let Err(e) = create_dir_all(parent) {
-                    panic!("Failed to create parent folder {}: {}", parent.display(), e);
-                }
-            }
-        }
-    } else if !path.exists() {
-        if let Err(e) = create_dir_all(path) {
-            panic!("Failed to create folder {}: {}", path.display(), e);
+        if let Some(parent) = path.parent()
+            && !parent.exists()
+            && let Err(e) = create_dir_all(parent)
+        {
+            panic!("Failed to create parent folder {}: {}", parent.display(), e);
         }
+    } else if !path.exists()
+        && let Err(e) = create_dir_all(path)
+    {
+        panic!("Failed to create folder {}: {}", path.display(), e);
     }
 }
+
+// Values of a float column - nulls (eg: items a product does not output) count as zero
+pub fn f64_values(df: &DataFrame, name: &str) -> PolarsResult<Vec<f64>> {
+    Ok(df
+        .column(name)?
+        .f64()?
+        .into_iter()
+        .map(|v| v.unwrap_or(0.0))
+        .collect())
+}
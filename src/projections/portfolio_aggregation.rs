@@ -0,0 +1,154 @@
+use crate::projections::annual_aggregation::{FLOW_COLUMNS, STOCK_COLUMNS};
+use polars::prelude::*;
+
+//---------------------------------------------------------------------------------------------------------
+// PUBLIC
+//---------------------------------------------------------------------------------------------------------
+// Total of each cashflow, decrement and stock column over the model points, by t and any model point
+// attributes - a block of the run at a time
+pub fn portfolio_df(
+    projected_df: &DataFrame,
+    model_points_df: &DataFrame,
+    split_columns: &[String],
+) -> PolarsResult<DataFrame> {
+    let available: Vec<String> = projected_df
+        .get_column_names()
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+    let has = |name: &str| available.iter().any(|a| a == name);
+    let items: Vec<&str> = FLOW_COLUMNS
+        .into_iter()
+        .chain(STOCK_COLUMNS)
+        .filter(|c| has(c))
+        .collect();
+
+    // Model point attributes to split by, unless projected already (eg: term)
+    let mut attribute_exprs = vec![col("id").cast(DataType::Int32)];
+    attribute_exprs.extend(
+        split_columns
+            .iter()
+            .filter(|c| !has(c))
+            .map(|c| col(c.as_str())),
+    );
+    let attributes_lf = model_points_df.clone().lazy().select(attribute_exprs);
+
+    projected_df
+        .clone()
+        .lazy()
+        .join(
+            attributes_lf,
+            [col("id")],
+            [col("id")],
+            JoinArgs::new(JoinType::Left),
+        )
+        .group_by(_keys(split_columns))
+        .agg(items.iter().map(|c| col(*c).sum()).collect::<Vec<Expr>>())
+        .collect()
+}
+
+// Portfolio totals of the whole run from the totals of its blocks
+pub fn combine_portfolio_dfs(
+    block_dfs: Vec<DataFrame>,
+    split_columns: &[String],
+) -> PolarsResult<DataFrame> {
+    // Products may output different columns - missing ones are null and left out of the sum
+    let has = |name: &str| {
+        block_dfs
+            .iter()
+            .any(|df| df.get_column_names().iter().any(|c| c.as_str() == name))
+    };
+    let aggs: Vec<Expr> = FLOW_COLUMNS
+        .into_iter()
+        .chain(STOCK_COLUMNS)
+        .filter(|c| has(c))
+        .map(|c| col(c).sum())
+        .collect();
+
+    let block_lfs: Vec<LazyFrame> = block_dfs.into_iter().map(|df| df.lazy()).collect();
+    if block_lfs.is_empty() {
+        return Ok(DataFrame::empty());
+    }
+
+    let args = UnionArgs {
+        diagonal: true,
+        ..Default::default()
+    };
+    let stacked_lf = concat(block_lfs, args)?;
+    let sort_cols: Vec<String> = split_columns
+        .iter()
+        .cloned()
+        .chain(["t".to_string()])
+        .collect();
+
+    stacked_lf
+        .group_by(_keys(split_columns))
+        .agg(aggs)
+        .sort(sort_cols, Default::default())
+        .collect()
+}
+
+//---------------------------------------------------------------------------------------------------------
+// PRIVATE
+//---------------------------------------------------------------------------------------------------------
+fn _keys(split_columns: &[String]) -> Vec<Expr> {
+    split_columns
+        .iter()
+        .map(|c| col(c.as_str()))
+        .chain([col("t")])
+        .collect()
+}
+
+//---------------------------------------------------------------------------------------------------------
+// UNIT TESTS
+//---------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assumptions::assumption_scenario::AssumptionScenario;
+    use crate::mp_gen::pricing_mp_gen::test_pricing_grid;
+    use crate::projections::projection_single_run::SingleRunSetup;
+
+    #[test]
+    fn test_fn_portfolio_df() {
+        let model_points_df = test_pricing_grid(&[30, 50]).generate().unwrap();
+        let result = SingleRunSetup::new(
+            "Portfolio",
+            model_points_df.clone(),
+            AssumptionScenario::new_by_name("pricing").unwrap(),
+        )
+        .with_portfolio_columns(&["gender"])
+        .projection_run()
+        .unwrap();
+        let split = vec!["gender".to_string()];
+
+        // Totals of two blocks combined are those of the run
+        let ids = result.projected_df.column("id").unwrap().i32().unwrap();
+        let first_block = result.projected_df.filter(&ids.lt_eq(4)).unwrap();
+        let second_block = result.projected_df.filter(&ids.gt(4)).unwrap();
+        let combined_df = combine_portfolio_dfs(
+            vec![
+                portfolio_df(&first_block, &model_points_df, &split).unwrap(),
+                portfolio_df(&second_block, &model_points_df, &split).unwrap(),
+            ],
+            &split,
+        )
+        .unwrap();
+
+        println!("{:?}", result.portfolio_df);
+
+        // One row per gender and month up to the longest term
+        assert_eq!(result.portfolio_df.height(), 2 * 241);
+        for name in ["premiums", "pols_if", "net_cf"] {
+            let combined = combined_df.column(name).unwrap().f64().unwrap();
+            let whole = result.portfolio_df.column(name).unwrap().f64().unwrap();
+            for (a, b) in combined.into_no_null_iter().zip(whole.into_no_null_iter()) {
+                assert!((a - b).abs() < 1e-6 * (1.0 + b.abs()));
+            }
+        }
+
+        // Portfolio premiums add up to the projection
+        let total = |df: &DataFrame| df.column("premiums").unwrap().f64().unwrap().sum().unwrap();
+        assert!((total(&result.portfolio_df) - total(&result.projected_df)).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,322 @@
+use crate::projections::helpers::f64_values;
+use crate::projections::projection_single_run::SingleRunResult;
+use polars::prelude::*;
+
+//---------------------------------------------------------------------------------------------------------
+// STRUCTS
+//---------------------------------------------------------------------------------------------------------
+// Profit test basis applied to the cashflows of a single run
+#[derive(Clone, Debug)]
+pub struct ProfitTest {
+    pub risk_discount_rate: f64,   // Annual rate used to discount profits
+    pub profit_column: String,     // Projected column holding the profit of each month
+    pub cell_columns: Vec<String>, // Model point attributes defining a pricing cell
+}
+
+impl Default for ProfitTest {
+    fn default() -> Self {
+        Self {
+            risk_discount_rate: 0.1,
+            profit_column: "net_cf".to_string(),
+            cell_columns: vec!["gender".to_string(), "term".to_string()],
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Debug)]
+pub struct ProfitTestResult {
+    pub mp_signature_df: DataFrame, // Annual profit by model point and projection year
+    pub cell_signature_df: DataFrame, // Annual profit by pricing cell and projection year
+    pub mp_metrics_df: DataFrame,   // One row per model point
+    pub cell_metrics_df: DataFrame, // One row per pricing cell
+}
+
+#[allow(dead_code)]
+impl ProfitTest {
+    pub fn with_risk_discount_rate(mut self, risk_discount_rate: f64) -> Self {
+        self.risk_discount_rate = risk_discount_rate;
+        self
+    }
+
+    pub fn with_profit_column(mut self, profit_column: &str) -> Self {
+        self.profit_column = profit_column.to_string();
+        self
+    }
+
+    pub fn with_cell_columns(mut self, cell_columns: &[&str]) -> Self {
+        self.cell_columns = cell_columns.iter().map(|c| c.to_string()).collect();
+        self
+    }
+
+    pub fn run(&self, result: &SingleRunResult) -> PolarsResult<ProfitTestResult> {
+        let df = self._cashflows_df(result)?;
+
+        let mp_keys = vec!["id".to_string()];
+        let mp_cf_df = _aggregate_by_month(&df, &mp_keys)?;
+        let cell_cf_df = _aggregate_by_month(&df, &self.cell_columns)?;
+
+        let profit_test_result = ProfitTestResult {
+            mp_signature_df: _profit_signature(&mp_cf_df, &mp_keys)?,
+            cell_signature_df: _profit_signature(&cell_cf_df, &self.cell_columns)?,
+            mp_metrics_df: self._metrics(&mp_cf_df, &mp_keys)?,
+            cell_metrics_df: self._metrics(&cell_cf_df, &self.cell_columns)?,
+        };
+
+        Ok(profit_test_result)
+    }
+
+    // Monthly profit and premiums, with the pricing cell attributes of each model point
+    fn _cashflows_df(&self, result: &SingleRunResult) -> PolarsResult<DataFrame> {
+        let mut cell_exprs = vec![col("id").cast(DataType::Int32)];
+        cell_exprs.extend(self.cell_columns.iter().map(|c| col(c.as_str())));
+
+        let cells_lf = result
+            .setup
+            .model_points_df
+            .clone()
+            .lazy()
+            .select(cell_exprs);
+
+        result
+            .projected_df
+            .clone()
+            .lazy()
+            // The last month of each model point is the end of the term - no cashflow
+            .filter(col("t").lt(col("t").max().over([col("id")])))
+            .select([
+                col("id"),
+                col("t"),
+                col(self.profit_column.as_str()).alias("profit"),
+                col("premiums"),
+            ])
+            .join(
+                cells_lf,
+                [col("id")],
+                [col("id")],
+                JoinArgs::new(JoinType::Left),
+            )
+            .collect()
+    }
+
+    fn _metrics(&self, cf_df: &DataFrame, keys: &[String]) -> PolarsResult<DataFrame> {
+        let mut pv_profit = Vec::new();
+        let mut pv_premiums = Vec::new();
+        let mut profit_margin = Vec::new();
+        let mut irr = Vec::new();
+        let mut break_even_year = Vec::new();
+        let mut keys_df = DataFrame::empty();
+
+        for group_df in cf_df.partition_by_stable(keys, true)? {
+            let t = f64_values(&group_df, "t")?;
+            let profit = f64_values(&group_df, "profit")?;
+            let premiums = f64_values(&group_df, "premiums")?;
+
+            let rdr = self.risk_discount_rate;
+            let group_pv_profit = _present_value(&t, &profit, rdr);
+            let group_pv_premiums = _present_value(&t, &premiums, rdr);
+
+            pv_profit.push(group_pv_profit);
+            pv_premiums.push(group_pv_premiums);
+            profit_margin.push(if group_pv_premiums != 0.0 {
+                Some(group_pv_profit / group_pv_premiums)
+            } else {
+                None
+            });
+            irr.push(_irr(&t, &profit));
+            break_even_year.push(_break_even_year(&t, &profit, rdr));
+
+            keys_df.vstack_mut(
+                &group_df
+                    .select(keys.iter().map(|k| k.as_str()))?
+                    .head(Some(1)),
+            )?;
+        }
+
+        let metrics_df = df![
+            "pv_profit" => pv_profit,
+            "pv_premiums" => pv_premiums,
+            "profit_margin" => profit_margin,
+            "irr" => irr,
+            "break_even_year" => break_even_year,
+        ]?;
+
+        keys_df.hstack(metrics_df.get_columns())
+    }
+}
+
+//---------------------------------------------------------------------------------------------------------
+// PRIVATE
+//---------------------------------------------------------------------------------------------------------
+fn _aggregate_by_month(df: &DataFrame, keys: &[String]) -> PolarsResult<DataFrame> {
+    let mut group_exprs: Vec<Expr> = keys.iter().map(|k| col(k.as_str())).collect();
+    group_exprs.push(col("t"));
+
+    let sort_cols: Vec<String> = keys.iter().cloned().chain(["t".to_string()]).collect();
+
+    df.clone()
+        .lazy()
+        .group_by(group_exprs)
+        .agg([col("profit").sum(), col("premiums").sum()])
+        .sort(sort_cols, Default::default())
+        .collect()
+}
+
+// Undiscounted profit of each projection year
+fn _profit_signature(cf_df: &DataFrame, keys: &[String]) -> PolarsResult<DataFrame> {
+    let mut group_exprs: Vec<Expr> = keys.iter().map(|k| col(k.as_str())).collect();
+    group_exprs.push(col("year"));
+
+    let sort_cols: Vec<String> = keys.iter().cloned().chain(["year".to_string()]).collect();
+
+    cf_df
+        .clone()
+        .lazy()
+        .with_column(
+            // Months are whole numbers, so integer division gives the year
+            (col("t").cast(DataType::Int32) / lit(12) + lit(1)).alias("year"),
+        )
+        .group_by(group_exprs)
+        .agg([col("profit").sum()])
+        .sort(sort_cols, Default::default())
+        .collect()
+}
+
+// Monthly cashflows discounted at an annual rate
+fn _present_value(t: &[f64], cf: &[f64], rate: f64) -> f64 {
+    t.iter()
+        .zip(cf.iter())
+        .map(|(&t, &cf)| cf * (1.0 + rate).powf(-t / 12.0))
+        .sum()
+}
+
+// Annual rate at which the PV of profits is nil, None if there is none.
+// Late losses can give several roots, so the highest one is bracketed by scanning down from 1000%
+// then refined by bisection.
+fn _irr(t: &[f64], profit: &[f64]) -> Option<f64> {
+    let rates: Vec<f64> = (0..=218).rev().map(|i| -0.9 + 0.05 * i as f64).collect();
+
+    let (mut high, mut low) = rates
+        .windows(2)
+        .map(|w| (w[0], w[1]))
+        .find(|&(high, low)| {
+            _present_value(t, profit, high) * _present_value(t, profit, low) <= 0.0
+        })?;
+
+    let pv_high = _present_value(t, profit, high);
+    for _ in 0..200 {
+        let mid = 0.5 * (low + high);
+        let pv_mid = _present_value(t, profit, mid);
+
+        if pv_mid * pv_high > 0.0 {
+            high = mid;
+        } else {
+            low = mid;
+        }
+
+        if high - low < 1e-10 {
+            break;
+        }
+    }
+
+    Some(0.5 * (low + high))
+}
+
+// First projection year from which the cumulative discounted profit stays non-negative
+fn _break_even_year(t: &[f64], profit: &[f64], rate: f64) -> Option<i32> {
+    let mut cumulative_by_year: Vec<(i32, f64)> = Vec::new();
+    let mut cumulative = 0.0;
+
+    for (&t, &profit) in t.iter().zip(profit.iter()) {
+        let year = (t / 12.0).floor() as i32 + 1;
+        cumulative += profit * (1.0 + rate).powf(-t / 12.0);
+
+        match cumulative_by_year.last_mut() {
+            Some((last_year, value)) if *last_year == year => *value = cumulative,
+            _ => cumulative_by_year.push((year, cumulative)),
+        }
+    }
+
+    let mut break_even = None;
+    for (year, value) in cumulative_by_year.iter().rev() {
+        if *value < 0.0 {
+            break;
+        }
+        break_even = Some(*year);
+    }
+
+    break_even
+}
+
+//---------------------------------------------------------------------------------------------------------
+// UNIT TESTS
+//---------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assumptions::assumption_scenario::AssumptionScenario;
+    use crate::mp_gen::pricing_mp_gen::PricingGrid;
+    use crate::projections::projection_single_run::SingleRunSetup;
+
+    #[test]
+    fn test_method_profit_test_run() {
+        let model_points_df = PricingGrid::new("s_model")
+            .with_ages(&[30, 40, 50])
+            .with_terms(&[10, 20])
+            .with_genders(&["M", "F"])
+            .with_sum_insureds(&[5_000_000.0])
+            .generate()
+            .unwrap();
+        let result = SingleRunSetup::new(
+            "Profit test",
+            model_points_df.clone(),
+            AssumptionScenario::new_by_name("pricing").unwrap(),
+        )
+        .projection_run()
+        .unwrap();
+
+        let profit_test = ProfitTest::default().with_risk_discount_rate(0.08);
+        let profit_test_result = profit_test.run(&result).unwrap();
+
+        println!("{:?}", profit_test_result.mp_metrics_df);
+        println!("{:?}", profit_test_result.cell_metrics_df);
+
+        assert_eq!(
+            profit_test_result.mp_metrics_df.height(),
+            model_points_df.height()
+        );
+        assert_eq!(profit_test_result.cell_metrics_df.height(), 4);
+
+        // Profit signature of a model point covers each year of its term
+        let signature_df = profit_test_result.mp_signature_df;
+        let first_mp =
+            signature_df.filter(&signature_df.column("id").unwrap().i32().unwrap().equal(1));
+        assert_eq!(first_mp.unwrap().height(), 10);
+
+        // Large policies are profitable - PV profit at the IRR is nil
+        let mp_metrics_df = profit_test_result.mp_metrics_df;
+        let irr = mp_metrics_df.column("irr").unwrap().f64().unwrap();
+        let pv_profit = mp_metrics_df.column("pv_profit").unwrap().f64().unwrap();
+        assert!(irr.into_iter().any(|irr| irr.is_some()));
+        for (irr, pv_profit) in irr.into_iter().zip(pv_profit) {
+            if let Some(irr) = irr {
+                // Profitable at the risk discount rate if and only if the IRR is above it
+                assert_eq!(irr > 0.08, pv_profit.unwrap() > 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fn_irr_and_break_even_year() {
+        // Strain of 100 at issue repaid by 30 at the start of each of the next 4 years
+        let t = [0.0, 12.0, 24.0, 36.0, 48.0];
+        let profit = [-100.0, 30.0, 30.0, 30.0, 30.0];
+
+        let irr = _irr(&t, &profit).unwrap();
+        assert!(_present_value(&t, &profit, irr).abs() < 1e-6);
+        assert!((irr - 0.0771).abs() < 1e-3);
+
+        assert_eq!(_break_even_year(&t, &profit, 0.0), Some(5));
+        assert_eq!(_break_even_year(&t, &profit, 0.5), None);
+    }
+}
@@ -1,26 +1,456 @@
 use crate::assumptions::assumption_scenario::AssumptionScenario;
 use chrono::NaiveDate;
-use ndarray::Array1;
 use polars::prelude::*;
+use std::collections::HashMap;
 
 mod asl_se_model;
+mod benefit_pattern;
+mod projection_block;
 mod s_model;
 mod se_model;
 
-use self::{asl_se_model::ASLSEModelPoint, s_model::SModelPoint, se_model::SEModelPoint};
+pub use self::{
+    asl_se_model::ASLSEModelPoint, benefit_pattern::BenefitPattern, s_model::SModelPoint,
+    se_model::SEModelPoint,
+};
 
-pub enum ModelPoint {
-    SModel(SModelPoint),
-    SEModel(SEModelPoint),
-    ASLSEModel(ASLSEModelPoint),
+//---------------------------------------------------------------------------------------------------------
+// TRAITS
+//---------------------------------------------------------------------------------------------------------
+/*
+A product model is a model point type that knows how to build itself from the model point DataFrame
+and how to project itself. New products (eg: whole life, endowment) implement this trait and are added
+to a ProductRegistry under the value of their `model` column - no change to the core is needed.
+*/
+pub trait ProductModel: Send + Sync {
+    // Value of the `model` column handled by this product
+    fn name() -> &'static str
+    where
+        Self: Sized;
+
+    // Columns (and their types) the model point DataFrame must provide
+    fn schema() -> Vec<(&'static str, DataType)>
+    where
+        Self: Sized;
+
+    // Construct all model points of a batch
+    fn from_df(df: &DataFrame) -> PolarsResult<Vec<Self>>
+    where
+        Self: Sized;
+
+    // Construct a single model point from one row
+    #[allow(dead_code)]
+    fn from_row(df: &DataFrame, row: usize) -> PolarsResult<Self>
+    where
+        Self: Sized,
+    {
+        Self::from_df(&df.slice(row as i64, 1))?
+            .pop()
+            .ok_or_else(|| PolarsError::ComputeError(format!("Row {row} not found").into()))
+    }
+
+    fn project(&self, assumptions: &AssumptionScenario) -> PolarsResult<LazyFrame>;
+
+    // Project a block of model points at once - products with a vectorised engine override this
+    fn project_block(
+        model_points: &[Self],
+        assumptions: &AssumptionScenario,
+    ) -> PolarsResult<DataFrame>
+    where
+        Self: Sized,
+    {
+        let all_lfs = model_points
+            .iter()
+            .map(|mp| mp.project(assumptions))
+            .collect::<PolarsResult<Vec<LazyFrame>>>()?;
+
+        concat(all_lfs, Default::default())?.collect()
+    }
+
+    // Project a block with the options of the run - by default every column is projected, then the
+    // requested ones are kept
+    fn project_block_with_options(
+        model_points: &[Self],
+        assumptions: &AssumptionScenario,
+        options: &ProjectionOptions,
+    ) -> PolarsResult<DataFrame>
+    where
+        Self: Sized,
+    {
+        options.select(Self::project_block(model_points, assumptions)?)
+    }
+}
+
+//---------------------------------------------------------------------------------------------------------
+// STRUCTS
+//---------------------------------------------------------------------------------------------------------
+pub type ModelPointsFactory = fn(&DataFrame) -> PolarsResult<Vec<Box<dyn ProductModel>>>;
+pub type BlockProjector =
+    fn(&DataFrame, &AssumptionScenario, &ProjectionOptions) -> PolarsResult<DataFrame>;
+
+// Settings of a run passed down to the product engines
+#[derive(Clone, Debug, Default)]
+pub struct ProjectionOptions {
+    pub columns: Option<Vec<String>>, // Output columns - all of them if None
+    pub valuation_date: Option<NaiveDate>, // Calendar date at t = 0 - from the issue dates if None
+}
+
+impl ProjectionOptions {
+    pub fn keeps(&self, name: &str) -> bool {
+        self.columns
+            .as_ref()
+            .is_none_or(|columns| columns.iter().any(|c| c == name))
+    }
+
+    // Requested columns out of a projection - those the product does not output are skipped
+    pub fn select(&self, df: DataFrame) -> PolarsResult<DataFrame> {
+        if self.columns.is_none() {
+            return Ok(df);
+        }
+
+        let columns: Vec<String> = df
+            .get_column_names()
+            .iter()
+            .filter(|c| self.keeps(c))
+            .map(|c| c.to_string())
+            .collect();
+        df.select(columns)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct ProductEntry {
+    schema: Vec<(&'static str, DataType)>,
+    factory: ModelPointsFactory,
+    projector: BlockProjector,
+}
+
+// Map the value of the `model` column to the product model implementing it
+#[derive(Clone, Debug)]
+pub struct ProductRegistry {
+    products: HashMap<String, ProductEntry>,
+}
+
+// Default registry holds the products shipped with the crate
+impl Default for ProductRegistry {
+    fn default() -> Self {
+        Self::empty()
+            .with_product::<SModelPoint>()
+            .with_product::<SEModelPoint>()
+            .with_product::<ASLSEModelPoint>()
+    }
+}
+
+#[allow(dead_code)]
+impl ProductRegistry {
+    pub fn empty() -> Self {
+        Self {
+            products: HashMap::new(),
+        }
+    }
+
+    pub fn with_product<P: ProductModel + 'static>(mut self) -> Self {
+        self.register::<P>();
+        self
+    }
+
+    pub fn register<P: ProductModel + 'static>(&mut self) {
+        let entry = ProductEntry {
+            schema: P::schema(),
+            factory: |df| {
+                let model_points = P::from_df(df)?;
+                Ok(model_points
+                    .into_iter()
+                    .map(|mp| Box::new(mp) as Box<dyn ProductModel>)
+                    .collect())
+            },
+            projector: |df, assumptions, options| {
+                P::project_block_with_options(&P::from_df(df)?, assumptions, options)
+            },
+        };
+
+        self.products.insert(P::name().to_string(), entry);
+    }
+
+    pub fn product_names(&self) -> Vec<&str> {
+        self.products.keys().map(|name| name.as_str()).collect()
+    }
+
+    pub fn schema(&self, model: &str) -> Option<&[(&'static str, DataType)]> {
+        self.products
+            .get(model)
+            .map(|entry| entry.schema.as_slice())
+    }
+
+    // Split the model point DataFrame by `model` and let each product build its own model points
+    pub fn model_points_from_df(&self, df: &DataFrame) -> PolarsResult<Vec<Box<dyn ProductModel>>> {
+        let mut model_points = Vec::with_capacity(df.height());
+
+        for (model_df, entry) in self._split_by_product(df)? {
+            model_points.extend((entry.factory)(&model_df)?);
+        }
+
+        Ok(model_points)
+    }
+
+    // Split the model point DataFrame by `model` and into blocks of at most block_size rows
+    pub fn blocks_from_df(
+        &self,
+        df: &DataFrame,
+        block_size: usize,
+    ) -> PolarsResult<Vec<(DataFrame, BlockProjector)>> {
+        let block_size = block_size.max(1);
+        let mut blocks = Vec::new();
+
+        for (model_df, entry) in self._split_by_product(df)? {
+            let mut offset = 0;
+            while offset < model_df.height() {
+                blocks.push((model_df.slice(offset as i64, block_size), entry.projector));
+                offset += block_size;
+            }
+        }
+
+        Ok(blocks)
+    }
+
+    fn _split_by_product(&self, df: &DataFrame) -> PolarsResult<Vec<(DataFrame, &ProductEntry)>> {
+        df.partition_by_stable(["model"], true)?
+            .into_iter()
+            .map(|model_df| {
+                let model = model_df
+                    .column("model")?
+                    .str()?
+                    .get(0)
+                    .unwrap_or_default()
+                    .to_string();
+
+                let entry = self.products.get(&model).ok_or_else(|| {
+                    PolarsError::ComputeError(format!("Unsupported model '{model}'").into())
+                })?;
+
+                _validate_schema(&model_df, &model, &entry.schema)?;
+
+                Ok((model_df, entry))
+            })
+            .collect()
+    }
+}
+
+//---------------------------------------------------------------------------------------------------------
+// PRIVATE
+//---------------------------------------------------------------------------------------------------------
+fn _validate_schema(
+    df: &DataFrame,
+    model: &str,
+    schema: &[(&'static str, DataType)],
+) -> PolarsResult<()> {
+    for (name, dtype) in schema.iter() {
+        let column = df.column(name).map_err(|_| {
+            PolarsError::ColumnNotFound(
+                format!("Column '{name}' is required by model '{model}'").into(),
+            )
+        })?;
+
+        column.cast(dtype).map_err(|_| {
+            PolarsError::SchemaMismatch(
+                format!("Column '{name}' of model '{model}' cannot be read as {dtype}").into(),
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
+// Typed column readers shared by the product models - nulls are read as defaults
+fn _i32_col(df: &DataFrame, name: &str) -> PolarsResult<Vec<i32>> {
+    let col = df.column(name)?.cast(&DataType::Int32)?;
+    Ok(col
+        .i32()?
+        .into_iter()
+        .map(|v| v.unwrap_or_default())
+        .collect())
+}
+
+fn _f64_col(df: &DataFrame, name: &str) -> PolarsResult<Vec<f64>> {
+    let col = df.column(name)?.cast(&DataType::Float64)?;
+    Ok(col
+        .f64()?
+        .into_iter()
+        .map(|v| v.unwrap_or_default())
+        .collect())
 }
 
-impl ModelPoint {
-    pub fn project(&self, assumptions: &AssumptionScenario) -> PolarsResult<LazyFrame> {
-        match self {
-            ModelPoint::SModel(mp) => mp.project(assumptions),
-            ModelPoint::SEModel(mp) => mp.project(assumptions),
-            ModelPoint::ASLSEModel(mp) => mp.project(assumptions),
+fn _str_col(df: &DataFrame, name: &str) -> PolarsResult<Vec<String>> {
+    let col = df.column(name)?.cast(&DataType::String)?;
+    Ok(col
+        .str()?
+        .into_iter()
+        .map(|v| v.unwrap_or_default().to_string())
+        .collect())
+}
+
+// Optional columns are read as None when missing
+fn _opt_str_col(df: &DataFrame, name: &str) -> PolarsResult<Vec<Option<String>>> {
+    match df.column(name) {
+        Ok(col) => Ok(col
+            .cast(&DataType::String)?
+            .str()?
+            .into_iter()
+            .map(|v| v.map(|v| v.to_string()))
+            .collect()),
+        Err(_) => Ok(vec![None; df.height()]),
+    }
+}
+
+fn _opt_i32_col(df: &DataFrame, name: &str, default: i32) -> PolarsResult<Vec<i32>> {
+    match df.column(name) {
+        Ok(col) => Ok(col
+            .cast(&DataType::Int32)?
+            .i32()?
+            .into_iter()
+            .map(|v| v.unwrap_or(default))
+            .collect()),
+        Err(_) => Ok(vec![default; df.height()]),
+    }
+}
+
+fn _opt_f64_col(df: &DataFrame, name: &str, default: f64) -> PolarsResult<Vec<f64>> {
+    match df.column(name) {
+        Ok(col) => Ok(col
+            .cast(&DataType::Float64)?
+            .f64()?
+            .into_iter()
+            .map(|v| v.unwrap_or(default))
+            .collect()),
+        Err(_) => Ok(vec![default; df.height()]),
+    }
+}
+
+fn _date_col(df: &DataFrame, name: &str) -> PolarsResult<Vec<NaiveDate>> {
+    let col = df.column(name)?.cast(&DataType::Date)?;
+
+    (0..col.len())
+        .map(|i| match col.get(i)? {
+            // Polars dates are days since 1970-01-01
+            AnyValue::Date(days) => NaiveDate::from_num_days_from_ce_opt(days + 719_163)
+                .ok_or_else(|| PolarsError::ComputeError("Invalid date".into())),
+            _ => Err(PolarsError::ComputeError(
+                format!("Column '{name}' has a missing date").into(),
+            )),
+        })
+        .collect()
+}
+
+//---------------------------------------------------------------------------------------------------------
+// UNIT TESTS
+//---------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp_gen::pricing_mp_gen::PricingGrid;
+    use crate::projections::projection_single_run::SingleRunSetup;
+
+    // Product defined outside the core, as a downstream crate would
+    struct FlatModelPoint {
+        id: i32,
+    }
+
+    impl ProductModel for FlatModelPoint {
+        fn name() -> &'static str {
+            "flat_model"
         }
+
+        fn schema() -> Vec<(&'static str, DataType)> {
+            vec![("id", DataType::Int32)]
+        }
+
+        fn from_df(df: &DataFrame) -> PolarsResult<Vec<Self>> {
+            Ok(_i32_col(df, "id")?
+                .into_iter()
+                .map(|id| FlatModelPoint { id })
+                .collect())
+        }
+
+        fn project(&self, _assumptions: &AssumptionScenario) -> PolarsResult<LazyFrame> {
+            Ok(df!["id" => [self.id], "t" => [0.0]]?.lazy())
+        }
+    }
+
+    #[test]
+    fn test_method_product_registry_model_points_from_df() {
+        let s_df = PricingGrid::default().generate().unwrap();
+        let flat_df = df!["model" => ["flat_model"; 3], "id" => [1, 2, 3]].unwrap();
+
+        let registry = ProductRegistry::default().with_product::<FlatModelPoint>();
+
+        assert_eq!(
+            registry.model_points_from_df(&s_df).unwrap().len(),
+            s_df.height()
+        );
+        assert_eq!(registry.model_points_from_df(&flat_df).unwrap().len(), 3);
+        assert!(
+            ProductRegistry::default()
+                .model_points_from_df(&flat_df)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_method_single_run_setup_registered_product() {
+        let s_df = PricingGrid::new("s_model")
+            .with_ages(&[30])
+            .with_terms(&[10])
+            .with_genders(&["M", "F"])
+            .with_sum_insureds(&[100_000.0])
+            .generate()
+            .unwrap();
+        let flat_df = df!["model" => ["flat_model"; 3], "id" => [101, 102, 103]].unwrap();
+        let args = UnionArgs {
+            diagonal: true,
+            ..Default::default()
+        };
+        let model_points_df = concat([s_df.clone().lazy(), flat_df.lazy()], args)
+            .unwrap()
+            .collect()
+            .unwrap();
+
+        let result = SingleRunSetup::new(
+            "Registered product",
+            model_points_df,
+            AssumptionScenario::new_by_name("pricing").unwrap(),
+        )
+        .with_product_registry(ProductRegistry::default().with_product::<FlatModelPoint>())
+        .projection_run()
+        .unwrap();
+
+        println!("{:?}", result.pv_df);
+
+        // Present values of the flat product are missing rather than failing the run
+        assert_eq!(result.pv_df.height(), s_df.height() + 3);
+        let pv_premiums = result.pv_df.column("pv_premiums").unwrap();
+        assert_eq!(pv_premiums.null_count(), 3);
+    }
+
+    #[test]
+    fn test_fn_product_registry_missing_column() {
+        let df = PricingGrid::default()
+            .generate()
+            .unwrap()
+            .drop("term")
+            .unwrap();
+
+        assert!(
+            ProductRegistry::default()
+                .model_points_from_df(&df)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_fn_product_model_from_row() {
+        let df = PricingGrid::default().generate().unwrap();
+        let mp = SModelPoint::from_row(&df, 5).unwrap();
+
+        assert_eq!(mp.id, 6);
     }
 }
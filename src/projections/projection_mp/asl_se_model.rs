@@ -1,8 +1,11 @@
+use super::projection_block::{PolicyBasis, ProjectionBlock};
 use super::*;
 
 //---------------------------------------------------------------------------------------------------------
 // STRUCT
 //---------------------------------------------------------------------------------------------------------
+#[allow(dead_code)]
+#[derive(Clone, Debug)]
 pub struct ASLSEModelPoint {
     pub model: String,
     pub id: i32,
@@ -15,14 +18,256 @@ pub struct ASLSEModelPoint {
     pub issue_date: NaiveDate,
     pub payment_freq: i32,
     pub payment_term: i32,
+    pub channel: Option<String>,
+    pub benefit_pattern: BenefitPattern,
+}
+
+impl ProductModel for ASLSEModelPoint {
+    fn name() -> &'static str {
+        "asl_se_model"
+    }
+
+    fn schema() -> Vec<(&'static str, DataType)> {
+        let mut schema = SEModelPoint::schema();
+        schema.push(("issue_date", DataType::Date));
+        schema.push(("payment_freq", DataType::Int32));
+        schema.push(("payment_term", DataType::Int32));
+        schema
+    }
+
+    fn from_df(df: &DataFrame) -> PolarsResult<Vec<Self>> {
+        let se_model_points = SEModelPoint::from_df(df)?;
+        let issue_date = _date_col(df, "issue_date")?;
+        let payment_freq = _i32_col(df, "payment_freq")?;
+        let payment_term = _i32_col(df, "payment_term")?;
+
+        let model_points = se_model_points
+            .into_iter()
+            .enumerate()
+            .map(|(i, mp)| ASLSEModelPoint {
+                model: mp.model,
+                id: mp.id,
+                entry_age: mp.entry_age,
+                gender: mp.gender,
+                term: mp.term,
+                policy_count: mp.policy_count,
+                sum_insured: mp.sum_insured,
+                duration_mth: mp.duration_mth,
+                issue_date: issue_date[i],
+                payment_freq: payment_freq[i],
+                payment_term: payment_term[i],
+                channel: mp.channel,
+                benefit_pattern: mp.benefit_pattern,
+            })
+            .collect();
+
+        Ok(model_points)
+    }
+
+    fn project(&self, assumptions: &AssumptionScenario) -> PolarsResult<LazyFrame> {
+        let df = Self::project_block(std::slice::from_ref(self), assumptions)?;
+        Ok(df.lazy())
+    }
+
+    // In-force projection with premiums paid payment_freq times a year over payment_term years
+    fn project_block(
+        model_points: &[Self],
+        assumptions: &AssumptionScenario,
+    ) -> PolarsResult<DataFrame> {
+        Self::project_block_with_options(model_points, assumptions, &ProjectionOptions::default())
+    }
+
+    fn project_block_with_options(
+        model_points: &[Self],
+        assumptions: &AssumptionScenario,
+        options: &ProjectionOptions,
+    ) -> PolarsResult<DataFrame> {
+        let s_model_points: Vec<SModelPoint> = model_points
+            .iter()
+            .map(|mp| mp.se_model_point().s_model_point())
+            .collect();
+        let bases: Vec<PolicyBasis> = model_points
+            .iter()
+            .map(|mp| PolicyBasis {
+                duration_mth: mp.duration_mth,
+                payment_freq: mp.payment_freq,
+                payment_term: mp.payment_term,
+                issue_date: Some(mp.issue_date),
+                start_date: None,
+            })
+            .collect();
+
+        ProjectionBlock::project_in_force(&s_model_points, &bases, assumptions, options)
+    }
 }
 
 impl ASLSEModelPoint {
-    pub fn project(&self, assumptions: &AssumptionScenario) -> PolarsResult<LazyFrame> {
-        todo!("Implement ASLSEModelPoint projection logic here");
+    // Policy attributes without the premium terms
+    fn se_model_point(&self) -> SEModelPoint {
+        SEModelPoint {
+            model: self.model.clone(),
+            id: self.id,
+            entry_age: self.entry_age,
+            gender: self.gender.clone(),
+            term: self.term,
+            policy_count: self.policy_count,
+            sum_insured: self.sum_insured,
+            duration_mth: self.duration_mth,
+            channel: self.channel.clone(),
+            payment_freq: self.payment_freq,
+            benefit_pattern: self.benefit_pattern,
+        }
     }
 }
 
 //---------------------------------------------------------------------------------------------------------
-// PRIVATE
+// UNIT TESTS
 //---------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn _asl_se_model_points_df(
+        duration_mth: i32,
+        payment_freq: i32,
+        payment_term: i32,
+    ) -> DataFrame {
+        let issue_date = NaiveDate::from_ymd_opt(2020, 3, 15).unwrap();
+
+        df![
+            "model" => ["asl_se_model"; 2],
+            "id" => [1, 2],
+            "entry_age" => [30, 45],
+            "gender" => ["M", "F"],
+            "term" => [10, 20],
+            "policy_count" => [1.0, 2.0],
+            "sum_insured" => [100_000.0, 250_000.0],
+            "duration_mth" => [duration_mth; 2],
+            "issue_date" => [issue_date; 2],
+            "payment_freq" => [payment_freq; 2],
+            "payment_term" => [payment_term; 2],
+        ]
+        .unwrap()
+    }
+
+    #[test]
+    fn test_fn_asl_se_model_payment_freq() {
+        let assumptions = AssumptionScenario::new_by_name("pricing").unwrap();
+        let model_points = ASLSEModelPoint::from_df(&_asl_se_model_points_df(0, 1, 5)).unwrap();
+
+        let df = ASLSEModelPoint::project_block(&model_points, &assumptions).unwrap();
+
+        println!("{df:?}");
+
+        // Annual installments in the first 5 policy years only
+        let paid_df = df
+            .clone()
+            .lazy()
+            .filter(col("premiums").gt(lit(0.0)))
+            .collect()
+            .unwrap();
+        let paid_t: Vec<f64> = paid_df
+            .column("t")
+            .unwrap()
+            .f64()
+            .unwrap()
+            .into_no_null_iter()
+            .collect();
+        assert_eq!(paid_t, [0.0, 12.0, 24.0, 36.0, 48.0].repeat(2));
+
+        // Installment premium still satisfies the equivalence principle
+        let check_df = df
+            .lazy()
+            .select([
+                (col("prem_pp") / (lit(1.0) + col("load_rate"))
+                    * col("pols_if")
+                    * col("discount_factor"))
+                .sum()
+                .alias("pv_net_premiums"),
+                (col("claims") * col("discount_factor"))
+                    .sum()
+                    .alias("pv_claims"),
+            ])
+            .collect()
+            .unwrap();
+        let pv_net_premiums = check_df.column("pv_net_premiums").unwrap().f64().unwrap();
+        let pv_claims = check_df.column("pv_claims").unwrap().f64().unwrap();
+        assert!((pv_net_premiums.get(0).unwrap() - pv_claims.get(0).unwrap()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_fn_asl_se_model_monthly_matches_se_model() {
+        let assumptions = AssumptionScenario::new_by_name("pricing").unwrap();
+        let df = _asl_se_model_points_df(18, 12, 10);
+
+        let asl_se_df =
+            ASLSEModelPoint::project_block(&ASLSEModelPoint::from_df(&df).unwrap(), &assumptions)
+                .unwrap();
+        let se_df = SEModelPoint::project_block(&SEModelPoint::from_df(&df).unwrap(), &assumptions)
+            .unwrap();
+
+        // Calendar date of the valuation month
+        let date = asl_se_df.column("date").unwrap().get(0).unwrap();
+        let expected = NaiveDate::from_ymd_opt(2021, 9, 15).unwrap();
+        assert_eq!(
+            date,
+            AnyValue::Date(
+                (expected - NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()).num_days() as i32
+            )
+        );
+
+        // Only the first model point has a premium term equal to its policy term
+        let asl_se_mp_df = asl_se_df
+            .drop("date")
+            .unwrap()
+            .filter(&asl_se_df.column("id").unwrap().i32().unwrap().equal(1))
+            .unwrap();
+        let se_mp_df = se_df
+            .filter(&se_df.column("id").unwrap().i32().unwrap().equal(1))
+            .unwrap();
+        assert!(asl_se_mp_df.equals(&se_mp_df));
+    }
+
+    #[test]
+    fn test_fn_asl_se_model_valuation_date() {
+        let assumptions = AssumptionScenario::new_by_name("pricing").unwrap();
+        let model_points = ASLSEModelPoint::from_df(&_asl_se_model_points_df(18, 12, 10)).unwrap();
+        let options = |valuation_date: NaiveDate| ProjectionOptions {
+            valuation_date: Some(valuation_date),
+            ..Default::default()
+        };
+
+        // Issued on 15 March 2020, 18 months in force on 15 September 2021
+        let valuation_date = NaiveDate::from_ymd_opt(2021, 9, 15).unwrap();
+        let df = ASLSEModelPoint::project_block_with_options(
+            &model_points,
+            &assumptions,
+            &options(valuation_date),
+        )
+        .unwrap();
+
+        println!("{df:?}");
+
+        let default_df = ASLSEModelPoint::project_block(&model_points, &assumptions).unwrap();
+        assert!(df.equals(&default_df));
+
+        // The duration does not match any other valuation date
+        let other_date = NaiveDate::from_ymd_opt(2021, 10, 1).unwrap();
+        assert!(
+            ASLSEModelPoint::project_block_with_options(
+                &model_points,
+                &assumptions,
+                &options(other_date)
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_fn_asl_se_model_invalid_payment_freq() {
+        let assumptions = AssumptionScenario::new_by_name("pricing").unwrap();
+        let model_points = ASLSEModelPoint::from_df(&_asl_se_model_points_df(0, 5, 5)).unwrap();
+
+        assert!(ASLSEModelPoint::project_block(&model_points, &assumptions).is_err());
+    }
+}
@@ -0,0 +1,98 @@
+use polars::prelude::*;
+
+//---------------------------------------------------------------------------------------------------------
+// STRUCTS
+//---------------------------------------------------------------------------------------------------------
+// Shape of the sum insured over the term, as a multiple of the sum insured at issue
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum BenefitPattern {
+    #[default]
+    Level,
+    // Linearly to nil at the end of the term
+    Decreasing,
+    // Outstanding balance of a monthly repayment loan
+    Amortising {
+        loan_rate: f64,
+    },
+    // Compound at each policy anniversary
+    Increasing {
+        rate: f64,
+    },
+    // Compound with the inflation assumption at each anniversary
+    CpiIncreasing,
+}
+
+impl BenefitPattern {
+    // Read from the optional `benefit_pattern` and `benefit_rate` model point columns
+    pub fn new(pattern: Option<&str>, rate: f64) -> PolarsResult<Self> {
+        match pattern.unwrap_or("level") {
+            "level" => Ok(BenefitPattern::Level),
+            "decreasing" => Ok(BenefitPattern::Decreasing),
+            "amortising" => Ok(BenefitPattern::Amortising { loan_rate: rate }),
+            "increasing" => Ok(BenefitPattern::Increasing { rate }),
+            "cpi" => Ok(BenefitPattern::CpiIncreasing),
+            other => Err(PolarsError::ComputeError(
+                format!("Unsupported benefit pattern '{other}'").into(),
+            )),
+        }
+    }
+
+    // Multiple of the sum insured at issue in force over the given policy month.
+    // inf_index is the inflation index accrued since issue, only used by CPI linked cover.
+    pub fn factor(&self, policy_mth: i32, term: i32, inf_index: f64) -> f64 {
+        let term_mth = term * 12;
+        let remaining_mth = (term_mth - policy_mth).max(0);
+
+        match *self {
+            BenefitPattern::Level => 1.0,
+            BenefitPattern::Decreasing => remaining_mth as f64 / term_mth as f64,
+            BenefitPattern::Amortising { loan_rate } if loan_rate != 0.0 => {
+                let v = (1.0 + loan_rate).powf(-1.0 / 12.0);
+                (1.0 - v.powi(remaining_mth)) / (1.0 - v.powi(term_mth))
+            }
+            BenefitPattern::Amortising { .. } => remaining_mth as f64 / term_mth as f64,
+            BenefitPattern::Increasing { rate } => (1.0 + rate).powi(policy_mth / 12),
+            BenefitPattern::CpiIncreasing => inf_index,
+        }
+    }
+}
+
+//---------------------------------------------------------------------------------------------------------
+// UNIT TESTS
+//---------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_method_benefit_pattern_factor() {
+        let level = BenefitPattern::new(None, 0.0).unwrap();
+        let decreasing = BenefitPattern::new(Some("decreasing"), 0.0).unwrap();
+        let amortising = BenefitPattern::new(Some("amortising"), 0.05).unwrap();
+        let increasing = BenefitPattern::new(Some("increasing"), 0.03).unwrap();
+        let cpi = BenefitPattern::new(Some("cpi"), 0.0).unwrap();
+
+        assert!(BenefitPattern::new(Some("unknown"), 0.0).is_err());
+
+        assert_eq!(level.factor(60, 10, 1.0), 1.0);
+        assert_eq!(decreasing.factor(60, 10, 1.0), 0.5);
+        assert_eq!(decreasing.factor(120, 10, 1.0), 0.0);
+
+        // Loan balance is repaid faster towards the end of the term
+        assert_eq!(amortising.factor(0, 10, 1.0), 1.0);
+        assert!(amortising.factor(60, 10, 1.0) > decreasing.factor(60, 10, 1.0));
+        assert!(amortising.factor(120, 10, 1.0).abs() < 1e-12);
+        assert_eq!(
+            BenefitPattern::new(Some("amortising"), 0.0)
+                .unwrap()
+                .factor(60, 10, 1.0),
+            0.5
+        );
+
+        // Increases at policy anniversaries only
+        assert_eq!(increasing.factor(11, 10, 1.0), 1.0);
+        assert!((increasing.factor(24, 10, 1.0) - 1.03_f64.powi(2)).abs() < 1e-12);
+        assert_eq!(cpi.factor(24, 10, 1.05), 1.05);
+    }
+}
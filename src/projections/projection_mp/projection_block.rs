@@ -0,0 +1,1814 @@
+use super::*;
+use crate::assumptions::assumption_scenario::{DecrementConversion, DecrementOrder, PremiumTarget};
+use crate::assumptions::commission::CommissionScale;
+use crate::assumptions::premium_rules::PremiumRules;
+use crate::assumptions::reinsurance::ReinsuranceTreaty;
+use crate::assumptions::tax::TaxBasis;
+use chrono::{Datelike, Months};
+use ndarray::{Array1, Array2, ArrayView1, Axis, Zip};
+
+//---------------------------------------------------------------------------------------------------------
+// STRUCTS
+//---------------------------------------------------------------------------------------------------------
+/*
+Vectorised projection of a block of model points, in the style of lifelib's BasicTerm_S:
+every variable is a 2-D array of shape (model points, projection months). Model points with a
+shorter term than the longest in the block simply ignore the trailing months when output.
+
+In-force policies (BasicTerm_SE) start the projection at their duration in months at the valuation
+date: policy year driven assumptions follow the attained duration, while spot and inflation rates
+run from the valuation date - by calendar year when the valuation date is known, so all model points
+share the same calendar timeline. Premiums are always set at issue, per installment, and only received on
+the payment dates within the premium paying term.
+*/
+// Policy state and premium terms of a model point, on top of its S model attributes
+#[derive(Clone, Debug)]
+pub(super) struct PolicyBasis {
+    pub duration_mth: i32,             // Months in force at t = 0
+    pub payment_freq: i32,             // Premium installments per year
+    pub payment_term: i32,             // Premium paying term in years
+    pub issue_date: Option<NaiveDate>, // Output the calendar date of each month when known
+    pub start_date: Option<NaiveDate>, // Calendar date at t = 0 - set from the valuation date
+}
+
+impl PolicyBasis {
+    // Premiums over the whole term, starting at issue
+    pub(super) fn new_business(model_point: &SModelPoint) -> Self {
+        Self {
+            duration_mth: 0,
+            payment_freq: model_point.payment_freq,
+            payment_term: model_point.term,
+            issue_date: None,
+            start_date: None,
+        }
+    }
+
+    fn _at_issue(&self) -> Self {
+        let months = Months::new(self.duration_mth as u32);
+        Self {
+            duration_mth: 0,
+            start_date: self.start_date.and_then(|d| d.checked_sub_months(months)),
+            ..self.clone()
+        }
+    }
+
+    // Calendar date of projection month j - from the valuation date, else from the issue date
+    fn _date(&self, j: usize) -> Option<NaiveDate> {
+        match (self.start_date, self.issue_date) {
+            (Some(start_date), _) => start_date.checked_add_months(Months::new(j as u32)),
+            (None, Some(issue_date)) => {
+                let months = Months::new((self.duration_mth + j as i32) as u32);
+                issue_date.checked_add_months(months)
+            }
+            (None, None) => None,
+        }
+    }
+}
+
+// Premium dependent cashflows of a model point
+struct PremiumCashflows {
+    premiums: Array1<f64>,
+    initial_commissions: Array1<f64>,
+    renewal_commissions: Array1<f64>,
+    clawback_commissions: Array1<f64>,
+    commissions: Array1<f64>,
+    premium_tax: Array1<f64>,
+    taxable_profit: Array1<f64>,
+    tax: Array1<f64>,
+    net_cf: Array1<f64>,
+}
+
+pub(super) struct ProjectionBlock {
+    // Model point attributes
+    id: Vec<i32>,
+    term: Vec<i32>,
+    sum_insured: Vec<f64>,
+    benefit_pattern: Vec<BenefitPattern>,
+    bases: Vec<PolicyBasis>,
+    proj_len: Vec<usize>, // Number of projection months (including t = 0) for each model point
+    net_prem: Array1<f64>, // Net premium per installment and policy set at issue
+    commission: CommissionScale,
+    tax_basis: TaxBasis,
+    modal_loading: Vec<f64>, // Loading on the net premium for the payment frequency
+    initial_comm_paid_pp: Vec<f64>, // Initial commission per policy received before t = 0
+
+    // Projection variables - shape (model points, months)
+    claim_pp: Array2<f64>,
+    t: Array2<f64>,
+    duration: Array2<i32>,
+    age: Array2<i32>,
+    proj_year: Array2<i32>, // Projection year - calendar years from the valuation date when known
+    prem_mth: Array2<f64>,  // 1.0 in the months a premium installment is due
+    initial_comm_rate: Array2<f64>,
+    renewal_comm_rate: Array2<f64>,
+    corporate_tax_rate: Array2<f64>,
+    mort_rate: Array2<f64>,
+    lapse_rate: Array2<f64>,
+    real_acq_exp_pp: Array2<f64>,
+    real_mtn_exp_pp: Array2<f64>,
+    inf_rate: Array2<f64>,
+    spot_rate: Array2<f64>,
+    load_rate: Array2<f64>,
+    spot_rate_mth: Array2<f64>,
+    discount_factor: Array2<f64>,
+    real_exp_pp: Array2<f64>,
+    inf_factor: Array2<f64>,
+    exp_pp: Array2<f64>,
+    mort_rate_mth: Array2<f64>,
+    lapse_rate_mth: Array2<f64>,
+    pols_if: Array2<f64>,
+    pols_maturity: Array2<f64>,
+    pols_death: Array2<f64>,
+    pols_lapse: Array2<f64>,
+    prem_pp: Array2<f64>,
+    expenses: Array2<f64>,
+    claims: Array2<f64>,
+    premiums: Array2<f64>,
+    initial_commissions: Array2<f64>,
+    renewal_commissions: Array2<f64>,
+    clawback_commissions: Array2<f64>,
+    commissions: Array2<f64>,
+    ceded_claims: Array2<f64>,
+    reinsurance_premiums: Array2<f64>,
+    reinsurance_commissions: Array2<f64>,
+    premium_tax: Array2<f64>,
+    taxable_profit: Array2<f64>, // Pre-tax profit less losses carried forward
+    tax: Array2<f64>,
+    net_cf: Array2<f64>, // After premium tax and corporation tax
+    net_premium_reserve_pp: Array2<f64>,
+    gross_premium_reserve_pp: Array2<f64>,
+    net_premium_reserve: Array2<f64>,
+    gross_premium_reserve: Array2<f64>,
+    assets_bop: Array2<f64>, // Assets backing the reserve at the start of the month
+    investment_income: Array2<f64>,
+    assets_eop: Array2<f64>, // Assets at the end of the month, before the surplus is released
+    statutory_profit: Array2<f64>,
+}
+
+impl ProjectionBlock {
+    // Project new business - every model point starts at issue
+    pub(super) fn project(
+        model_points: &[SModelPoint],
+        assumptions: &AssumptionScenario,
+    ) -> PolarsResult<DataFrame> {
+        Self::project_with_options(model_points, assumptions, &ProjectionOptions::default())
+    }
+
+    pub(super) fn project_with_options(
+        model_points: &[SModelPoint],
+        assumptions: &AssumptionScenario,
+        options: &ProjectionOptions,
+    ) -> PolarsResult<DataFrame> {
+        let bases: Vec<PolicyBasis> = model_points.iter().map(PolicyBasis::new_business).collect();
+        Self::project_in_force(model_points, &bases, assumptions, options)
+    }
+
+    // Project in-force policies over their remaining term from their duration in months
+    pub(super) fn project_in_force(
+        model_points: &[SModelPoint],
+        bases: &[PolicyBasis],
+        assumptions: &AssumptionScenario,
+        options: &ProjectionOptions,
+    ) -> PolarsResult<DataFrame> {
+        _validate_bases(model_points, bases)?;
+
+        // Every model point starts on the valuation date when given
+        if let Some(valuation_date) = options.valuation_date {
+            _validate_valuation_date(model_points, bases, valuation_date)?;
+        }
+        let bases: Vec<PolicyBasis> = bases
+            .iter()
+            .map(|b| PolicyBasis {
+                start_date: options.valuation_date.or(b.start_date),
+                ..b.clone()
+            })
+            .collect();
+        let bases = bases.as_slice();
+
+        // Premiums are set at issue, so in-force policies are first projected from issue -
+        // on the pricing basis when the scenario has one
+        let issue_bases: Vec<PolicyBasis> = bases.iter().map(|b| b._at_issue()).collect();
+        let issue_block = match &assumptions.pricing_basis {
+            Some(pricing_basis) => {
+                Some(Self::_run(model_points, &issue_bases, None, pricing_basis)?)
+            }
+            None if bases.iter().all(|b| b.duration_mth == 0) => None,
+            None => Some(Self::_run(model_points, &issue_bases, None, assumptions)?),
+        };
+
+        Self::_run(model_points, bases, issue_block.as_ref(), assumptions)?._to_df(options)
+    }
+
+    fn _run(
+        model_points: &[SModelPoint],
+        bases: &[PolicyBasis],
+        issue_block: Option<&Self>,
+        assumptions: &AssumptionScenario,
+    ) -> PolarsResult<Self> {
+        let mut block = Self::_initialize(model_points, bases);
+
+        block._map_assumptions(model_points, assumptions)?;
+        block._claim_pp();
+        block._discount_factor();
+        block._exp_pp();
+        block._policies_movement(
+            model_points,
+            assumptions.decrement_conversion,
+            assumptions.decrement_order,
+            assumptions.mass_lapse,
+        );
+        block._reinsurance(&assumptions.reinsurance);
+        block._complete_projection(
+            issue_block,
+            assumptions.premium_target.as_ref(),
+            &assumptions.premium_rules,
+        )?;
+        block._reserves(assumptions.reserve_floor);
+        block._surplus(assumptions.earned_rate);
+
+        Ok(block)
+    }
+
+    // ------------------Intialize block------------------
+    fn _initialize(model_points: &[SModelPoint], bases: &[PolicyBasis]) -> Self {
+        let n = model_points.len();
+        let proj_len: Vec<usize> = model_points
+            .iter()
+            .zip(bases.iter())
+            .map(|(mp, b)| (mp.term * 12 - b.duration_mth + 1) as usize) // Remaining months in the term
+            .collect();
+        let width = proj_len.iter().copied().max().unwrap_or(0);
+
+        let zeros = Array2::<f64>::zeros((n, width));
+
+        let t = Array2::from_shape_fn((n, width), |(_, j)| j as f64);
+        let duration =
+            Array2::from_shape_fn((n, width), |(i, j)| (bases[i].duration_mth + j as i32) / 12);
+        let age = Array2::from_shape_fn((n, width), |(i, j)| {
+            model_points[i].entry_age + duration[[i, j]]
+        });
+        let proj_year = Array2::from_shape_fn((n, width), |(i, j)| match bases[i].start_date {
+            Some(start_date) => bases[i]
+                ._date(j)
+                .map_or((j / 12) as i32, |date| date.year() - start_date.year()),
+            None => (j / 12) as i32,
+        });
+
+        // Installments are due every 12 / payment_freq months from issue, within the paying term
+        let prem_mth = Array2::from_shape_fn((n, width), |(i, j)| {
+            let policy_mth = bases[i].duration_mth + j as i32;
+            let is_due = policy_mth % (12 / bases[i].payment_freq) == 0
+                && policy_mth < bases[i].payment_term * 12;
+            if is_due { 1.0 } else { 0.0 }
+        });
+        Self {
+            id: model_points.iter().map(|mp| mp.id).collect(),
+            term: model_points.iter().map(|mp| mp.term).collect(),
+            sum_insured: model_points.iter().map(|mp| mp.sum_insured).collect(),
+            benefit_pattern: model_points.iter().map(|mp| mp.benefit_pattern).collect(),
+            bases: bases.to_vec(),
+            proj_len,
+            net_prem: Array1::zeros(n),
+            commission: CommissionScale::default(),
+            tax_basis: TaxBasis::default(),
+            modal_loading: vec![0.0; n],
+            initial_comm_paid_pp: vec![0.0; n],
+            claim_pp: zeros.clone(),
+            t,
+            duration,
+            age,
+            proj_year,
+            prem_mth,
+            initial_comm_rate: zeros.clone(),
+            renewal_comm_rate: zeros.clone(),
+            corporate_tax_rate: zeros.clone(),
+            mort_rate: zeros.clone(),
+            lapse_rate: zeros.clone(),
+            real_acq_exp_pp: zeros.clone(),
+            real_mtn_exp_pp: zeros.clone(),
+            inf_rate: zeros.clone(),
+            spot_rate: zeros.clone(),
+            load_rate: zeros.clone(),
+            spot_rate_mth: zeros.clone(),
+            discount_factor: zeros.clone(),
+            real_exp_pp: zeros.clone(),
+            inf_factor: zeros.clone(),
+            exp_pp: zeros.clone(),
+            mort_rate_mth: zeros.clone(),
+            lapse_rate_mth: zeros.clone(),
+            pols_if: zeros.clone(),
+            pols_maturity: zeros.clone(),
+            pols_death: zeros.clone(),
+            pols_lapse: zeros.clone(),
+            prem_pp: zeros.clone(),
+            expenses: zeros.clone(),
+            claims: zeros.clone(),
+            premiums: zeros.clone(),
+            initial_commissions: zeros.clone(),
+            renewal_commissions: zeros.clone(),
+            clawback_commissions: zeros.clone(),
+            commissions: zeros.clone(),
+            ceded_claims: zeros.clone(),
+            reinsurance_premiums: zeros.clone(),
+            reinsurance_commissions: zeros.clone(),
+            premium_tax: zeros.clone(),
+            taxable_profit: zeros.clone(),
+            tax: zeros.clone(),
+            net_cf: zeros.clone(),
+            net_premium_reserve_pp: zeros.clone(),
+            gross_premium_reserve_pp: zeros.clone(),
+            net_premium_reserve: zeros.clone(),
+            gross_premium_reserve: zeros.clone(),
+            assets_bop: zeros.clone(),
+            investment_income: zeros.clone(),
+            assets_eop: zeros.clone(),
+            statutory_profit: zeros,
+        }
+    }
+
+    // ------------------Map assumptions------------------
+    fn _map_assumptions(
+        &mut self,
+        model_points: &[SModelPoint],
+        assumptions: &AssumptionScenario,
+    ) -> PolarsResult<()> {
+        // Mortality assumption is looked up by gender and attained age
+        let mut mort_tables: HashMap<String, Vec<f64>> = HashMap::new();
+        for mp in model_points.iter() {
+            if !mort_tables.contains_key(&mp.gender) {
+                let table = __mort_table(&assumptions.mort, &mp.gender)?;
+                mort_tables.insert(mp.gender.clone(), table);
+            }
+        }
+
+        Zip::indexed(&mut self.mort_rate)
+            .and(&self.age)
+            .for_each(|(i, _), rate, &age| {
+                *rate = __lookup(&mort_tables[&model_points[i].gender], age);
+            });
+
+        // Lapse, Expenses and Loading are looked up by policy year,
+        // Inflation and Spot rate by projection year - both are the same for new business
+        let lookups = [
+            (&mut self.lapse_rate, &assumptions.lapse, &self.duration),
+            (&mut self.real_acq_exp_pp, &assumptions.acq, &self.duration),
+            (&mut self.real_mtn_exp_pp, &assumptions.mtn, &self.duration),
+            (&mut self.inf_rate, &assumptions.inf, &self.proj_year),
+            (&mut self.spot_rate, &assumptions.spot, &self.proj_year),
+            (&mut self.load_rate, &assumptions.load, &self.duration),
+        ];
+
+        for (values, lookup_df, year) in lookups {
+            let table = __duration_table(lookup_df)?;
+            Zip::from(values)
+                .and(year)
+                .for_each(|value, &year| *value = __lookup(&table, year));
+        }
+
+        // Modal loading by payment frequency
+        self.modal_loading = self
+            .bases
+            .iter()
+            .map(|b| assumptions.modal_loading(b.payment_freq))
+            .collect();
+
+        // Commission rates by policy year, term and channel - initial in the first policy year
+        self.commission = assumptions.commission.clone();
+        for (i, mp) in model_points.iter().enumerate() {
+            for j in 0..self.proj_len[i] {
+                let year = self.duration[[i, j]] + 1;
+                let rate = self.commission.rate(year, mp.term, mp.channel.as_deref());
+                if year == 1 {
+                    self.initial_comm_rate[[i, j]] = rate;
+                } else {
+                    self.renewal_comm_rate[[i, j]] = rate;
+                }
+            }
+        }
+
+        // Corporation tax rate by projection year
+        self.tax_basis = assumptions.tax.clone();
+        let tax_basis = &self.tax_basis;
+        Zip::from(&mut self.corporate_tax_rate)
+            .and(&self.proj_year)
+            .for_each(|rate, &year| *rate = tax_basis.corporate_tax_rate(year + 1));
+
+        Ok(())
+    }
+
+    // ------------------Claim per policy------------------
+    fn _claim_pp(&mut self) {
+        for i in 0..self.id.len() {
+            // Inflation index since issue - rates before the valuation date are taken as the first year's
+            let mut inf_index = (1.0 + self.inf_rate[[i, 0]]).powi(self.duration[[i, 0]]);
+
+            for j in 0..self.proj_len[i] {
+                let policy_mth = self.bases[i].duration_mth + j as i32;
+                if j > 0 && policy_mth % 12 == 0 {
+                    inf_index *= 1.0 + self.inf_rate[[i, j]];
+                }
+
+                self.claim_pp[[i, j]] = self.sum_insured[i]
+                    * self.benefit_pattern[i].factor(policy_mth, self.term[i], inf_index);
+            }
+        }
+    }
+
+    // ------------------Discount factor------------------
+    fn _discount_factor(&mut self) {
+        // Spot rate monthly
+        self.spot_rate_mth = self.spot_rate.mapv(|r| (1.0 + r).powf(1.0 / 12.0) - 1.0);
+
+        // Discount factor
+        self.discount_factor = Zip::from(&self.spot_rate_mth)
+            .and(&self.t)
+            .map_collect(|&r, &t| 1.0 / (1.0 + r).powf(t));
+    }
+
+    // ------------------Expense per policy------------------
+    fn _exp_pp(&mut self) {
+        // Total real expense per policy
+        self.real_exp_pp = &self.real_acq_exp_pp + &self.real_mtn_exp_pp;
+
+        // Inflation factor - for flat curve only
+        self.inf_factor = Zip::from(&self.inf_rate)
+            .and(&self.t)
+            .map_collect(|&r, &t| (1.0 + r).powf(t / 12.0));
+
+        // Adjusted expense per policy
+        self.exp_pp = &self.real_exp_pp * &self.inf_factor;
+    }
+
+    // ------------------Policy movement------------------
+    fn _policies_movement(
+        &mut self,
+        model_points: &[SModelPoint],
+        conversion: DecrementConversion,
+        order: DecrementOrder,
+        mass_lapse: f64,
+    ) {
+        // Monthly decrement rate - UDD depends on the month within the policy year
+        let month_in_year = |(i, j): (usize, usize)| (self.bases[i].duration_mth + j as i32) % 12;
+        self.mort_rate_mth = Array2::from_shape_fn(self.mort_rate.dim(), |ij| {
+            conversion.monthly_rate(self.mort_rate[ij], month_in_year(ij))
+        });
+        self.lapse_rate_mth = Array2::from_shape_fn(self.lapse_rate.dim(), |ij| {
+            conversion.monthly_rate(self.lapse_rate[ij], month_in_year(ij))
+        });
+
+        for (i, mp) in model_points.iter().enumerate() {
+            let maturity_mth = (self.term[i] * 12 - self.bases[i].duration_mth) as usize;
+
+            // The last month of each model point is left empty
+            for j in 0..(self.proj_len[i] - 1) {
+                let pols_if = if j == 0 {
+                    mp.policy_count
+                } else {
+                    self.pols_if[[i, j - 1]]
+                        - self.pols_maturity[[i, j - 1]]
+                        - self.pols_death[[i, j - 1]]
+                        - self.pols_lapse[[i, j - 1]]
+                };
+
+                let pols_maturity = if j == maturity_mth {
+                    pols_if // Maturity at the end of the term
+                } else {
+                    0.0 // No maturity before term ends
+                };
+                // Mass lapse at the start of the projection, before the monthly decrements
+                let pols_mass_lapse = if j == 0 {
+                    (pols_if - pols_maturity) * mass_lapse
+                } else {
+                    0.0
+                };
+                let (pols_death, pols_lapse) = order.apply(
+                    pols_if - pols_maturity - pols_mass_lapse,
+                    self.mort_rate_mth[[i, j]],
+                    self.lapse_rate_mth[[i, j]],
+                );
+                let pols_lapse = pols_lapse + pols_mass_lapse;
+
+                self.pols_if[[i, j]] = pols_if;
+                self.pols_maturity[[i, j]] = pols_maturity;
+                self.pols_death[[i, j]] = pols_death;
+                self.pols_lapse[[i, j]] = pols_lapse;
+            }
+        }
+    }
+
+    // ------------------Reinsurance------------------
+    // Ceded claims and YRT premiums on the ceded sum at risk - they do not depend on the premium
+    fn _reinsurance(&mut self, treaty: &ReinsuranceTreaty) {
+        for i in 0..self.id.len() {
+            for j in 0..self.proj_len[i] {
+                let ceded_pp = treaty.ceded(self.claim_pp[[i, j]]);
+                let reinsurance_prem_pp = ceded_pp * treaty.yrt_rate(self.age[[i, j]]) / 12.0;
+
+                self.ceded_claims[[i, j]] = ceded_pp * self.pols_death[[i, j]];
+                self.reinsurance_premiums[[i, j]] = reinsurance_prem_pp * self.pols_if[[i, j]];
+                self.reinsurance_commissions[[i, j]] =
+                    treaty.commission_rate * self.reinsurance_premiums[[i, j]];
+            }
+        }
+    }
+
+    // ------------------Complete projection------------------
+    fn _complete_projection(
+        &mut self,
+        issue_block: Option<&Self>,
+        premium_target: Option<&PremiumTarget>,
+        premium_rules: &PremiumRules,
+    ) -> PolarsResult<()> {
+        // Portfolio claims and expenses
+        self.claims = &self.claim_pp * &self.pols_death;
+        self.expenses = &self.exp_pp * &self.pols_if;
+
+        match issue_block {
+            // In-force policies keep the premiums set at issue
+            Some(issue_block) => {
+                self.net_prem = issue_block.net_prem.clone();
+                for (i, b) in self.bases.iter().enumerate() {
+                    let d = b.duration_mth as usize;
+                    for j in 0..self.proj_len[i] {
+                        self.prem_pp[[i, j]] = issue_block.prem_pp[[i, j + d]];
+                    }
+
+                    // Initial commission received before the valuation date can still be clawed back
+                    self.initial_comm_paid_pp[i] = (0..d)
+                        .map(|k| {
+                            issue_block.prem_pp[[i, k]] * issue_block.initial_comm_rate[[i, k]]
+                        })
+                        .sum();
+                }
+            }
+            None => {
+                // Net premium per installment: PV of claims over PV of premium annuities
+                let pv_claims = (&self.claims * &self.discount_factor).sum_axis(Axis(1));
+                let prem_annuities =
+                    (&self.pols_if * &self.discount_factor * &self.prem_mth).sum_axis(Axis(1));
+                self.net_prem = Zip::from(&pv_claims)
+                    .and(&prem_annuities)
+                    .map_collect(|&pv, &ann| if ann != 0.0 { pv / ann } else { 0.0 });
+                let net_prem = &self.net_prem;
+                let modal_loading = &self.modal_loading;
+
+                // Loaded premium - only in the months an installment is due
+                self.prem_pp = Zip::indexed(&self.load_rate)
+                    .and(&self.prem_mth)
+                    .map_collect(|(i, _), &load, &due| {
+                        (1.0 + load) * (1.0 + modal_loading[i]) * net_prem[i] * due
+                    });
+
+                // Level premium meeting the target, allowing for all cashflows
+                if let Some(premium_target) = premium_target {
+                    for i in 0..self.id.len() {
+                        let premium = self._solve_premium(i, premium_target, premium_rules)?;
+                        let prem_pp = self.prem_mth.row(i).mapv(|due| premium * due);
+                        self.prem_pp.row_mut(i).assign(&prem_pp);
+                    }
+                }
+
+                // Published premium - rounded rate plus policy fee, subject to the minimum
+                let sum_insured = &self.sum_insured;
+                let bases = &self.bases;
+                Zip::indexed(&mut self.prem_pp)
+                    .and(&self.prem_mth)
+                    .for_each(|(i, _), prem_pp, &due| {
+                        if due != 0.0 {
+                            *prem_pp = premium_rules.apply(
+                                *prem_pp,
+                                sum_insured[i],
+                                bases[i].payment_freq,
+                            );
+                        }
+                    });
+            }
+        }
+
+        for i in 0..self.id.len() {
+            let cf = self._cashflows_row(i, &self.prem_pp.row(i));
+            self.premiums.row_mut(i).assign(&cf.premiums);
+            self.initial_commissions
+                .row_mut(i)
+                .assign(&cf.initial_commissions);
+            self.renewal_commissions
+                .row_mut(i)
+                .assign(&cf.renewal_commissions);
+            self.clawback_commissions
+                .row_mut(i)
+                .assign(&cf.clawback_commissions);
+            self.commissions.row_mut(i).assign(&cf.commissions);
+            self.premium_tax.row_mut(i).assign(&cf.premium_tax);
+            self.taxable_profit.row_mut(i).assign(&cf.taxable_profit);
+            self.tax.row_mut(i).assign(&cf.tax);
+            self.net_cf.row_mut(i).assign(&cf.net_cf);
+        }
+
+        Ok(())
+    }
+
+    // Premium dependent cashflows of a model point for the given premium per policy
+    fn _cashflows_row(&self, i: usize, prem_pp: &ArrayView1<f64>) -> PremiumCashflows {
+        // Portfolio premiums
+        let pols_if = self.pols_if.row(i);
+        let premiums = prem_pp * &pols_if;
+
+        // Commission as a share of premium from the commission scale
+        let initial_commissions = &premiums * &self.initial_comm_rate.row(i);
+        let renewal_commissions = &premiums * &self.renewal_comm_rate.row(i);
+
+        // Initial commission received to date is repaid on policies lapsing in the clawback period
+        let mut clawback_commissions = Array1::<f64>::zeros(premiums.len());
+        if self.commission.clawback.is_some() {
+            let mut paid_pp = self.initial_comm_paid_pp[i];
+            for j in 0..self.proj_len[i] {
+                paid_pp += prem_pp[j] * self.initial_comm_rate[[i, j]];
+                let policy_mth = self.bases[i].duration_mth + j as i32;
+                clawback_commissions[j] =
+                    self.pols_lapse[[i, j]] * paid_pp * self.commission.clawback_rate(policy_mth);
+            }
+        }
+
+        let commissions = &initial_commissions + &renewal_commissions - &clawback_commissions;
+        let premium_tax = &premiums * self.tax_basis.premium_tax_rate;
+        let pre_tax_profit =
+            &premiums - &premium_tax - self.expenses.row(i) - self.claims.row(i) - &commissions;
+
+        // Net of reinsurance
+        let pre_tax_profit = pre_tax_profit + self.ceded_claims.row(i)
+            - self.reinsurance_premiums.row(i)
+            + self.reinsurance_commissions.row(i);
+
+        // Losses are carried forward and set off against the next profits of the model point
+        let mut taxable_profit = Array1::<f64>::zeros(premiums.len());
+        let mut tax = Array1::<f64>::zeros(premiums.len());
+        let mut loss_carried_forward = 0.0;
+        for j in 0..self.proj_len[i] {
+            taxable_profit[j] = (pre_tax_profit[j] - loss_carried_forward).max(0.0);
+            loss_carried_forward = (loss_carried_forward - pre_tax_profit[j]).max(0.0);
+            tax[j] = taxable_profit[j] * self.corporate_tax_rate[[i, j]];
+        }
+
+        let net_cf = &pre_tax_profit - &tax;
+
+        PremiumCashflows {
+            premiums,
+            initial_commissions,
+            renewal_commissions,
+            clawback_commissions,
+            commissions,
+            premium_tax,
+            taxable_profit,
+            tax,
+            net_cf,
+        }
+    }
+
+    // Secant method on the level premium - commissions depend on the premium being solved.
+    // The policy fee is part of the published premium, so the premium solved is the one before the fee;
+    // rounding and the minimum premium are applied afterwards.
+    fn _solve_premium(
+        &self,
+        i: usize,
+        premium_target: &PremiumTarget,
+        premium_rules: &PremiumRules,
+    ) -> PolarsResult<f64> {
+        let (rate, margin) = premium_target.rate_and_margin();
+        let discount: Array1<f64> = self.t.row(i).mapv(|t| (1.0 + rate).powf(-t / 12.0));
+        let fee = premium_rules.installment_fee(self.bases[i].payment_freq);
+
+        // PV of profit in excess of the target margin
+        let objective = |premium: f64| {
+            let prem_pp = self.prem_mth.row(i).mapv(|due| (premium + fee) * due);
+            let cf = self._cashflows_row(i, &prem_pp.view());
+            ((&cf.net_cf - &(&cf.premiums * margin)) * &discount).sum()
+        };
+
+        // Start from the loaded net premium
+        let loaded_prem =
+            (1.0 + self.load_rate[[i, 0]]) * (1.0 + self.modal_loading[i]) * self.net_prem[i];
+        let (mut p0, mut p1) = (loaded_prem, 1.5 * loaded_prem + 1.0);
+        let (mut f0, mut f1) = (objective(p0), objective(p1));
+
+        for _ in 0..100 {
+            if (p1 - p0).abs() <= 1e-10 * (1.0 + p1.abs()) {
+                return Ok(p1);
+            }
+
+            if f1 == f0 {
+                break;
+            }
+
+            let p2 = p1 - f1 * (p1 - p0) / (f1 - f0);
+            (p0, f0) = (p1, f1);
+            (p1, f1) = (p2, objective(p2));
+        }
+
+        Err(PolarsError::ComputeError(
+            format!(
+                "Model point {}: premium solver did not converge",
+                self.id[i]
+            )
+            .into(),
+        ))
+    }
+
+    // ------------------Reserves------------------
+    // Prospective reserves: PV at t of future outgo less future income, from t onward
+    fn _reserves(&mut self, floor: bool) {
+        // Net premium basis: claims against the net premium set at issue
+        let net_premiums = Zip::indexed(&self.pols_if)
+            .and(&self.prem_mth)
+            .map_collect(|(i, _), &pols_if, &due| self.net_prem[i] * due * pols_if);
+        let net_outgo = &self.claims - &net_premiums;
+
+        // Gross premium basis: all cashflows net of reinsurance, before corporation tax
+        let gross_outgo = -(&self.net_cf + &self.tax);
+
+        self.net_premium_reserve = self._prospective_value(&net_outgo, floor);
+        self.gross_premium_reserve = self._prospective_value(&gross_outgo, floor);
+
+        // Reserve per policy in force
+        let per_policy = |reserve: &Array2<f64>| {
+            Zip::from(reserve)
+                .and(&self.pols_if)
+                .map_collect(|&v, &pols_if| if pols_if > 0.0 { v / pols_if } else { 0.0 })
+        };
+        self.net_premium_reserve_pp = per_policy(&self.net_premium_reserve);
+        self.gross_premium_reserve_pp = per_policy(&self.gross_premium_reserve);
+    }
+
+    fn _prospective_value(&self, outgo: &Array2<f64>, floor: bool) -> Array2<f64> {
+        let mut reserve = Array2::<f64>::zeros(outgo.raw_dim());
+
+        for (i, &len) in self.proj_len.iter().enumerate() {
+            // Accumulate discounted outgo backwards from the end of the term
+            let mut pv = 0.0;
+            for j in (0..len).rev() {
+                let discount_factor = self.discount_factor[[i, j]];
+                pv += outgo[[i, j]] * discount_factor;
+
+                let value = if discount_factor != 0.0 {
+                    pv / discount_factor
+                } else {
+                    0.0
+                };
+                reserve[[i, j]] = if floor { value.max(0.0) } else { value };
+            }
+        }
+
+        reserve
+    }
+
+    // ------------------Surplus------------------
+    // Assets equal to the gross premium reserve are held at the start of each month - none at issue.
+    // They earn investment income after the month's cashflows, and the excess over the reserve at the
+    // end of the month is released as statutory profit.
+    fn _surplus(&mut self, earned_rate: Option<f64>) {
+        for (i, &len) in self.proj_len.iter().enumerate() {
+            for j in 0..len {
+                let earned_rate_mth = match earned_rate {
+                    Some(rate) => (1.0 + rate).powf(1.0 / 12.0) - 1.0,
+                    None => self.spot_rate_mth[[i, j]],
+                };
+
+                let assets_bop = if j == 0 && self.bases[i].duration_mth == 0 {
+                    0.0
+                } else {
+                    self.gross_premium_reserve[[i, j]]
+                };
+                let investment_income = (assets_bop + self.net_cf[[i, j]]) * earned_rate_mth;
+                let assets_eop = assets_bop + self.net_cf[[i, j]] + investment_income;
+                let reserve_eop = if j + 1 < len {
+                    self.gross_premium_reserve[[i, j + 1]]
+                } else {
+                    0.0
+                };
+
+                self.assets_bop[[i, j]] = assets_bop;
+                self.investment_income[[i, j]] = investment_income;
+                self.assets_eop[[i, j]] = assets_eop;
+                self.statutory_profit[[i, j]] = assets_eop - reserve_eop;
+            }
+        }
+    }
+
+    // ------------------Output------------------
+    // Flatten the block to one row per model point and month, dropping months beyond each term - only
+    // the columns requested by the run are flattened
+    fn _to_df(&self, options: &ProjectionOptions) -> PolarsResult<DataFrame> {
+        let len = &self.proj_len;
+
+        let mut columns = Vec::new();
+        macro_rules! output {
+            ($($name:literal => $values:expr),* $(,)?) => {
+                $(
+                    if options.keeps($name) {
+                        columns.push(Column::new($name.into(), $values));
+                    }
+                )*
+            };
+        }
+
+        output![
+            "id" => __repeat(&self.id, len),
+            "term" => __repeat(&self.term, len),
+            "sum_insured" => __repeat(&self.sum_insured, len),
+            "claim_pp" => __flatten(&self.claim_pp, len),
+            "t" => __flatten(&self.t, len),
+            "duration" => __flatten(&self.duration, len),
+            "age" => __flatten(&self.age, len),
+            "mort_rate" => __flatten(&self.mort_rate, len),
+            "lapse_rate" => __flatten(&self.lapse_rate, len),
+            "real_acq_exp_pp" => __flatten(&self.real_acq_exp_pp, len),
+            "real_mtn_exp_pp" => __flatten(&self.real_mtn_exp_pp, len),
+            "inf_rate" => __flatten(&self.inf_rate, len),
+            "spot_rate" => __flatten(&self.spot_rate, len),
+            "load_rate" => __flatten(&self.load_rate, len),
+            "spot_rate_mth" => __flatten(&self.spot_rate_mth, len),
+            "discount_factor" => __flatten(&self.discount_factor, len),
+            "real_exp_pp" => __flatten(&self.real_exp_pp, len),
+            "inf_factor" => __flatten(&self.inf_factor, len),
+            "exp_pp" => __flatten(&self.exp_pp, len),
+            "mort_rate_mth" => __flatten(&self.mort_rate_mth, len),
+            "lapse_rate_mth" => __flatten(&self.lapse_rate_mth, len),
+            "pols_if" => __flatten(&self.pols_if, len),
+            "pols_maturity" => __flatten(&self.pols_maturity, len),
+            "pols_death" => __flatten(&self.pols_death, len),
+            "pols_lapse" => __flatten(&self.pols_lapse, len),
+            "prem_pp" => __flatten(&self.prem_pp, len),
+            "expenses" => __flatten(&self.expenses, len),
+            "claims" => __flatten(&self.claims, len),
+            "premiums" => __flatten(&self.premiums, len),
+            "initial_commissions" => __flatten(&self.initial_commissions, len),
+            "renewal_commissions" => __flatten(&self.renewal_commissions, len),
+            "clawback_commissions" => __flatten(&self.clawback_commissions, len),
+            "commissions" => __flatten(&self.commissions, len),
+            "ceded_claims" => __flatten(&self.ceded_claims, len),
+            "reinsurance_premiums" => __flatten(&self.reinsurance_premiums, len),
+            "reinsurance_commissions" => __flatten(&self.reinsurance_commissions, len),
+            "premium_tax" => __flatten(&self.premium_tax, len),
+            "taxable_profit" => __flatten(&self.taxable_profit, len),
+            "tax" => __flatten(&self.tax, len),
+            "net_cf" => __flatten(&self.net_cf, len),
+            "net_premium_reserve_pp" => __flatten(&self.net_premium_reserve_pp, len),
+            "gross_premium_reserve_pp" => __flatten(&self.gross_premium_reserve_pp, len),
+            "net_premium_reserve" => __flatten(&self.net_premium_reserve, len),
+            "gross_premium_reserve" => __flatten(&self.gross_premium_reserve, len),
+            "assets_bop" => __flatten(&self.assets_bop, len),
+            "investment_income" => __flatten(&self.investment_income, len),
+            "assets_eop" => __flatten(&self.assets_eop, len),
+            "statutory_profit" => __flatten(&self.statutory_profit, len),
+        ];
+        let mut df = DataFrame::new(columns)?;
+
+        // Calendar date of each projection month, when the valuation date or issue dates are known
+        let has_date = !self.bases.is_empty() && self.bases.iter().all(|b| b._date(0).is_some());
+        if has_date && options.keeps("date") {
+            let date = self
+                .bases
+                .iter()
+                .zip(len.iter())
+                .flat_map(|(b, &len)| (0..len).map(move |j| b._date(j)))
+                .collect::<Vec<Option<NaiveDate>>>();
+
+            // Right after t
+            let at = ["id", "term", "sum_insured", "claim_pp", "t"]
+                .into_iter()
+                .filter(|c| options.keeps(c))
+                .count();
+            df.insert_column(at, Column::new("date".into(), date))?;
+        }
+
+        Ok(df)
+    }
+}
+
+//---------------------------------------------------------------------------------------------------------
+// PRIVATE
+//---------------------------------------------------------------------------------------------------------
+// In-force duration must fall within the policy term, premiums within the policy term and
+// installments evenly spread over the year
+fn _validate_bases(model_points: &[SModelPoint], bases: &[PolicyBasis]) -> PolarsResult<()> {
+    for (mp, b) in model_points.iter().zip(bases.iter()) {
+        let error = if b.duration_mth < 0 || b.duration_mth > mp.term * 12 {
+            format!(
+                "duration_mth {} is outside the policy term of {} months",
+                b.duration_mth,
+                mp.term * 12
+            )
+        } else if ![1, 2, 3, 4, 6, 12].contains(&b.payment_freq) {
+            format!("payment_freq {} must divide 12", b.payment_freq)
+        } else if b.payment_term < 1 || b.payment_term > mp.term {
+            format!(
+                "payment_term {} must be between 1 and the policy term of {} years",
+                b.payment_term, mp.term
+            )
+        } else {
+            continue;
+        };
+
+        return Err(PolarsError::ComputeError(
+            format!("Model point {}: {error}", mp.id).into(),
+        ));
+    }
+
+    Ok(())
+}
+
+// Model points with an issue date must reach their duration on the valuation date
+fn _validate_valuation_date(
+    model_points: &[SModelPoint],
+    bases: &[PolicyBasis],
+    valuation_date: NaiveDate,
+) -> PolarsResult<()> {
+    for (mp, b) in model_points.iter().zip(bases.iter()) {
+        let Some(issue_date) = b.issue_date else {
+            continue;
+        };
+
+        let date = issue_date + Months::new(b.duration_mth as u32);
+        if date != valuation_date {
+            return Err(PolarsError::ComputeError(
+                format!(
+                    "Model point {}: issue_date {issue_date} plus duration_mth {} is {date}, not the valuation date {valuation_date}",
+                    mp.id, b.duration_mth
+                )
+                .into(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+// Mortality rates indexed by age for the column matching the gender
+fn __mort_table(mort_df: &DataFrame, gender: &str) -> PolarsResult<Vec<f64>> {
+    // Find the column name according to gender
+    let suffix = format!("_{}", gender.to_lowercase());
+    let mort_col_name = mort_df
+        .get_column_names()
+        .iter()
+        .find(|&col| col.ends_with(&suffix))
+        .ok_or_else(|| {
+            PolarsError::ComputeError(
+                format!("Mortality column with suffix '{suffix}' not found").into(),
+            )
+        })?
+        .as_str();
+
+    __indexed_table(mort_df, "age", mort_col_name, 0)
+}
+
+// Rates indexed by duration (policy year - 1) from the second column of an assumption table
+fn __duration_table(lookup_df: &DataFrame) -> PolarsResult<Vec<f64>> {
+    let col_name = lookup_df.get_column_names()[1].as_str();
+    __indexed_table(lookup_df, "year", col_name, 1)
+}
+
+fn __indexed_table(
+    df: &DataFrame,
+    key_col: &str,
+    value_col: &str,
+    key_offset: i32,
+) -> PolarsResult<Vec<f64>> {
+    let keys = df.column(key_col)?.cast(&DataType::Int32)?;
+    let values = df.column(value_col)?.cast(&DataType::Float64)?;
+
+    let mut table = Vec::new();
+    for (key, value) in keys.i32()?.into_iter().zip(values.f64()?) {
+        if let (Some(key), Some(value)) = (key, value) {
+            let idx = key - key_offset;
+            if idx >= 0 {
+                let idx = idx as usize;
+                if table.len() <= idx {
+                    table.resize(idx + 1, 0.0);
+                }
+                table[idx] = value;
+            }
+        }
+    }
+
+    Ok(table)
+}
+
+// Repeat a model point attribute over its projection months
+fn __repeat<T: Copy>(values: &[T], proj_len: &[usize]) -> Vec<T> {
+    values
+        .iter()
+        .zip(proj_len.iter())
+        .flat_map(|(&value, &len)| std::iter::repeat_n(value, len))
+        .collect()
+}
+
+// Flatten a (model points, months) array row by row, keeping each model point's own months
+fn __flatten<T: Copy>(values: &Array2<T>, proj_len: &[usize]) -> Vec<T> {
+    values
+        .rows()
+        .into_iter()
+        .zip(proj_len.iter())
+        .flat_map(|(row, &len)| row.iter().copied().take(len).collect::<Vec<T>>())
+        .collect()
+}
+
+// Values outside the table are 0.0 - same as a left join filled with 0.0
+fn __lookup(table: &[f64], idx: i32) -> f64 {
+    if idx < 0 {
+        0.0
+    } else {
+        table.get(idx as usize).copied().unwrap_or(0.0)
+    }
+}
+
+//---------------------------------------------------------------------------------------------------------
+// UNIT TESTS
+//---------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assumptions::assumption_scenario::{
+        DecrementConversion, DecrementOrder, PremiumTarget,
+    };
+    use crate::assumptions::commission::CommissionScale;
+    use crate::assumptions::premium_rules::{PremiumRules, RoundingBasis};
+    use crate::assumptions::reinsurance::ReinsuranceTreaty;
+    use crate::assumptions::tax::TaxBasis;
+    use crate::mp_gen::pricing_mp_gen::test_pricing_grid;
+    use crate::projections::helpers::f64_values;
+
+    fn _model_points() -> Vec<SModelPoint> {
+        let df = test_pricing_grid(&[25, 45]).generate().unwrap();
+
+        SModelPoint::from_df(&df).unwrap()
+    }
+
+    #[test]
+    fn test_fn_projection_block_project() {
+        let model_points = _model_points();
+        let assumptions = AssumptionScenario::new_by_name("pricing").unwrap();
+
+        let df = ProjectionBlock::project(&model_points, &assumptions).unwrap();
+
+        println!("{df:?}");
+
+        // One row per month including t = 0 for each model point
+        let rows: i32 = model_points.iter().map(|mp| mp.term * 12 + 1).sum();
+        assert_eq!(df.height(), rows as usize);
+
+        // Equivalence principle: PV of net premiums equals PV of claims for each model point
+        let check_df = df
+            .lazy()
+            .group_by([col("id")])
+            .agg([
+                (col("prem_pp") / (lit(1.0) + col("load_rate"))
+                    * col("pols_if")
+                    * col("discount_factor"))
+                .sum()
+                .alias("pv_net_premiums"),
+                (col("claims") * col("discount_factor"))
+                    .sum()
+                    .alias("pv_claims"),
+            ])
+            .collect()
+            .unwrap();
+
+        let pv_net_premiums = check_df.column("pv_net_premiums").unwrap().f64().unwrap();
+        let pv_claims = check_df.column("pv_claims").unwrap().f64().unwrap();
+        for (prem, claims) in pv_net_premiums.into_iter().zip(pv_claims) {
+            assert!((prem.unwrap() - claims.unwrap()).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_fn_projection_block_reserves() {
+        let model_points = _model_points();
+        let assumptions = AssumptionScenario::new_by_name("pricing").unwrap();
+
+        let df = ProjectionBlock::project(&model_points, &assumptions).unwrap();
+
+        let mut offset = 0;
+        for mp in model_points.iter() {
+            let len = (mp.term * 12 + 1) as usize;
+            let mp_df = df.slice(offset as i64, len);
+            offset += len;
+
+            let (claims, premiums, load_rate) = (
+                f64_values(&mp_df, "claims").unwrap(),
+                f64_values(&mp_df, "premiums").unwrap(),
+                f64_values(&mp_df, "load_rate").unwrap(),
+            );
+            let (net_cf, tax) = (
+                f64_values(&mp_df, "net_cf").unwrap(),
+                f64_values(&mp_df, "tax").unwrap(),
+            );
+            let discount_factor = f64_values(&mp_df, "discount_factor").unwrap();
+
+            // Equivalence principle at issue
+            let net = f64_values(&mp_df, "net_premium_reserve").unwrap();
+            assert!(net[0].abs() < 1e-6);
+
+            // Each reserve is the month's outgo plus the discounted reserve a month later - the
+            // month before maturity holds that month's outgo only
+            let net_outgo: Vec<f64> = (0..len)
+                .map(|j| claims[j] - premiums[j] / (1.0 + load_rate[j]))
+                .collect();
+            let gross_outgo: Vec<f64> = (0..len).map(|j| -(net_cf[j] + tax[j])).collect();
+            for (reserve, outgo) in [
+                (net, net_outgo),
+                (
+                    f64_values(&mp_df, "gross_premium_reserve").unwrap(),
+                    gross_outgo,
+                ),
+            ] {
+                for j in 0..len - 1 {
+                    let expected =
+                        outgo[j] + reserve[j + 1] * discount_factor[j + 1] / discount_factor[j];
+                    assert!((reserve[j] - expected).abs() < 1e-9 * (1.0 + expected.abs()));
+                }
+                assert!((reserve[len - 2] - outgo[len - 2]).abs() < 1e-9);
+
+                // Nothing is held at the end of the term
+                assert_eq!(reserve[len - 1], 0.0);
+            }
+        }
+        assert_eq!(offset, df.height());
+
+        // In-force policies run off from their duration to the same zero reserve at the end of the term
+        let duration_mth = 30;
+        let bases: Vec<PolicyBasis> = model_points
+            .iter()
+            .map(|mp| PolicyBasis {
+                duration_mth,
+                ..PolicyBasis::new_business(mp)
+            })
+            .collect();
+        let in_force_df = ProjectionBlock::project_in_force(
+            &model_points,
+            &bases,
+            &assumptions,
+            &ProjectionOptions::default(),
+        )
+        .unwrap();
+
+        let mut offset = 0;
+        for mp in model_points.iter() {
+            let len = (mp.term * 12 - duration_mth + 1) as usize;
+            let mp_df = in_force_df.slice(offset as i64, len);
+            offset += len;
+
+            let duration = mp_df.column("duration").unwrap().i32().unwrap();
+            assert_eq!(duration.get(len - 1), Some(mp.term));
+            assert_eq!(duration.get(len - 2), Some(mp.term - 1));
+            for name in ["net_premium_reserve", "gross_premium_reserve"] {
+                let reserve = f64_values(&mp_df, name).unwrap();
+                assert!(reserve[len - 2] != 0.0);
+                assert_eq!(reserve[len - 1], 0.0);
+            }
+        }
+        assert_eq!(offset, in_force_df.height());
+
+        // Floored reserves are the unfloored ones capped below at zero
+        let floored_df =
+            ProjectionBlock::project(&model_points, &assumptions.with_reserve_floor(true)).unwrap();
+        for name in ["net_premium_reserve", "gross_premium_reserve"] {
+            let reserve = df.column(name).unwrap().f64().unwrap();
+            let floored = floored_df.column(name).unwrap().f64().unwrap();
+            for (v, f) in reserve.into_iter().zip(floored) {
+                assert_eq!(v.unwrap().max(0.0), f.unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn test_fn_projection_block_premium_target() {
+        let model_points = _model_points();
+        let pricing = AssumptionScenario::new_by_name("pricing").unwrap();
+
+        let targets = [
+            PremiumTarget::ZeroProfit {
+                risk_discount_rate: 0.08,
+            },
+            PremiumTarget::ProfitMargin {
+                margin: 0.1,
+                risk_discount_rate: 0.08,
+            },
+            PremiumTarget::Irr { hurdle_rate: 0.12 },
+        ];
+
+        for target in targets {
+            let (rate, margin) = target.rate_and_margin();
+            let assumptions = pricing.clone().with_premium_target(target);
+
+            let df = ProjectionBlock::project(&model_points, &assumptions)
+                .unwrap()
+                .lazy()
+                .with_column(
+                    (lit(1.0) + lit(rate))
+                        .pow(-col("t") / lit(12.0))
+                        .alias("rdr_discount_factor"),
+                )
+                .group_by([col("id")])
+                .agg([
+                    (col("net_cf") * col("rdr_discount_factor"))
+                        .sum()
+                        .alias("pv_profit"),
+                    (col("premiums") * col("rdr_discount_factor"))
+                        .sum()
+                        .alias("pv_premiums"),
+                ])
+                .collect()
+                .unwrap();
+
+            let pv_profit = df.column("pv_profit").unwrap().f64().unwrap();
+            let pv_premiums = df.column("pv_premiums").unwrap().f64().unwrap();
+            for (profit, premiums) in pv_profit.into_iter().zip(pv_premiums) {
+                let (profit, premiums) = (profit.unwrap(), premiums.unwrap());
+                assert!(premiums > 0.0);
+                assert!((profit - margin * premiums).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fn_projection_block_premium_target_with_premium_rules() {
+        let model_points = _model_points();
+        let target = PremiumTarget::ZeroProfit {
+            risk_discount_rate: 0.08,
+        };
+        let assumptions = AssumptionScenario::new_by_name("pricing")
+            .unwrap()
+            .with_premium_target(target)
+            .with_premium_rules(PremiumRules::default().with_policy_fee(120.0));
+
+        let df = ProjectionBlock::project(&model_points, &assumptions)
+            .unwrap()
+            .lazy()
+            .group_by([col("id")])
+            .agg([(col("net_cf") * (lit(1.08)).pow(-col("t") / lit(12.0)))
+                .sum()
+                .alias("pv_profit")])
+            .collect()
+            .unwrap();
+
+        println!("{df:?}");
+
+        // The policy fee is part of the premium meeting the target
+        let pv_profit = df.column("pv_profit").unwrap().f64().unwrap();
+        for profit in pv_profit.into_no_null_iter() {
+            assert!(profit.abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_fn_projection_block_commission_scale() {
+        let model_points = _model_points();
+        let commission = CommissionScale::new()
+            .with_rate(1, 0.6)
+            .with_term_rate(1, 20, 0.9)
+            .with_rate(2, 0.05)
+            .with_rate(3, 0.05)
+            .with_clawback(24, 1.0);
+        let assumptions = AssumptionScenario::new_by_name("pricing")
+            .unwrap()
+            .with_commission(commission);
+
+        let df = ProjectionBlock::project(&model_points, &assumptions)
+            .unwrap()
+            .lazy()
+            .group_by([col("term"), col("duration")])
+            .agg([
+                col("premiums").sum(),
+                col("initial_commissions").sum(),
+                col("renewal_commissions").sum(),
+                col("clawback_commissions").sum(),
+                col("commissions").sum(),
+            ])
+            .sort(["term", "duration"], Default::default())
+            .collect()
+            .unwrap();
+
+        println!("{df:?}");
+
+        let value = |term: i32, duration: i32, name: &str| {
+            let row = df
+                .clone()
+                .lazy()
+                .filter(
+                    col("term")
+                        .eq(lit(term))
+                        .and(col("duration").eq(lit(duration))),
+                )
+                .collect()
+                .unwrap();
+            row.column(name).unwrap().f64().unwrap().get(0).unwrap()
+        };
+
+        // Rates by policy year and term
+        let premiums = value(10, 0, "premiums");
+        assert!((value(10, 0, "initial_commissions") - 0.6 * premiums).abs() < 1e-9);
+        assert!(
+            (value(20, 0, "initial_commissions") - 0.9 * value(20, 0, "premiums")).abs() < 1e-9
+        );
+        assert!(
+            (value(10, 1, "renewal_commissions") - 0.05 * value(10, 1, "premiums")).abs() < 1e-9
+        );
+        assert_eq!(value(10, 3, "renewal_commissions"), 0.0);
+
+        // Clawback on early lapses only, netted off the total commission
+        assert!(value(10, 0, "clawback_commissions") > 0.0);
+        assert!(value(10, 1, "clawback_commissions") > 0.0);
+        assert_eq!(value(10, 2, "clawback_commissions"), 0.0);
+        assert!(
+            (value(10, 1, "commissions")
+                - (value(10, 1, "renewal_commissions") - value(10, 1, "clawback_commissions")))
+            .abs()
+                < 1e-9
+        );
+    }
+
+    #[test]
+    fn test_fn_projection_block_premium_rules() {
+        let model_points = _model_points();
+        let assumptions = AssumptionScenario::new_by_name("pricing").unwrap();
+        let premium_rules = PremiumRules::default()
+            .with_rounding(RoundingBasis::PerSumInsured(1000.0), 2)
+            .with_policy_fee(36.0);
+
+        let df = ProjectionBlock::project(&model_points, &assumptions).unwrap();
+        let ruled_df = ProjectionBlock::project(
+            &model_points,
+            &assumptions.clone().with_premium_rules(premium_rules),
+        )
+        .unwrap();
+
+        println!("{ruled_df:?}");
+
+        // Rate per 1,000 sum insured to two decimals, plus the monthly share of the fee
+        let prem_pp = f64_values(&df, "prem_pp").unwrap();
+        let ruled_prem_pp = f64_values(&ruled_df, "prem_pp").unwrap();
+        for (premium, ruled_premium) in prem_pp.iter().zip(ruled_prem_pp.iter()) {
+            if *premium == 0.0 {
+                assert_eq!(*ruled_premium, 0.0);
+                continue;
+            }
+            let rate = (ruled_premium - 3.0) / 100.0;
+            assert!((rate * 100.0 - (rate * 100.0).round()).abs() < 1e-9);
+            assert!((rate - premium / 100.0).abs() <= 0.005 + 1e-9);
+        }
+
+        // Cashflows use the published premium
+        for ((premiums, prem_pp), pols_if) in f64_values(&ruled_df, "premiums")
+            .unwrap()
+            .iter()
+            .zip(ruled_prem_pp.iter())
+            .zip(f64_values(&ruled_df, "pols_if").unwrap())
+        {
+            assert!((premiums - prem_pp * pols_if).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_fn_projection_block_reinsurance() {
+        let model_points = _model_points();
+        let treaty = ReinsuranceTreaty::new()
+            .with_quota_share(0.5)
+            .with_yrt_rate(0, 0.002)
+            .with_commission_rate(0.1);
+        let assumptions = AssumptionScenario::new_by_name("pricing").unwrap();
+
+        let df = ProjectionBlock::project(&model_points, &assumptions).unwrap();
+        let ceded_df =
+            ProjectionBlock::project(&model_points, &assumptions.clone().with_reinsurance(treaty))
+                .unwrap();
+
+        println!("{ceded_df:?}");
+
+        // Half of each claim recovered, YRT premium on half the sum at risk
+        let claims = f64_values(&ceded_df, "claims").unwrap();
+        let ceded_claims = f64_values(&ceded_df, "ceded_claims").unwrap();
+        let pols_if = f64_values(&ceded_df, "pols_if").unwrap();
+        let reinsurance_premiums = f64_values(&ceded_df, "reinsurance_premiums").unwrap();
+        let reinsurance_commissions = f64_values(&ceded_df, "reinsurance_commissions").unwrap();
+        for k in 0..ceded_df.height() {
+            assert!((ceded_claims[k] - 0.5 * claims[k]).abs() < 1e-9);
+            assert!((reinsurance_premiums[k] - 50_000.0 * 0.002 / 12.0 * pols_if[k]).abs() < 1e-9);
+            assert!((reinsurance_commissions[k] - 0.1 * reinsurance_premiums[k]).abs() < 1e-9);
+        }
+
+        // Net of reinsurance cashflows feed the profit and gross premium reserve
+        let net_cf = f64_values(&df, "net_cf").unwrap();
+        let ceded_net_cf = f64_values(&ceded_df, "net_cf").unwrap();
+        for k in 0..ceded_df.height() {
+            let reinsurance_cf =
+                ceded_claims[k] - reinsurance_premiums[k] + reinsurance_commissions[k];
+            assert!((ceded_net_cf[k] - (net_cf[k] + reinsurance_cf)).abs() < 1e-6);
+        }
+        assert!(
+            !f64_values(&df, "gross_premium_reserve")
+                .unwrap()
+                .iter()
+                .zip(f64_values(&ceded_df, "gross_premium_reserve").unwrap())
+                .all(|(a, b)| (a - b).abs() < 1e-9)
+        );
+    }
+
+    #[test]
+    fn test_fn_projection_block_tax() {
+        let model_points = _model_points();
+        let tax = TaxBasis::new()
+            .with_premium_tax_rate(0.02)
+            .with_corporate_tax_rate(1, 0.25);
+        // Priced for a profit after tax
+        let assumptions = AssumptionScenario::new_by_name("pricing")
+            .unwrap()
+            .with_tax(tax)
+            .with_premium_target(PremiumTarget::ProfitMargin {
+                margin: 0.05,
+                risk_discount_rate: 0.08,
+            });
+
+        let df = ProjectionBlock::project(&model_points, &assumptions).unwrap();
+
+        println!("{df:?}");
+
+        let id = df.column("id").unwrap().i32().unwrap();
+        let premiums = f64_values(&df, "premiums").unwrap();
+        let premium_tax = f64_values(&df, "premium_tax").unwrap();
+        let taxable_profit = f64_values(&df, "taxable_profit").unwrap();
+        let tax = f64_values(&df, "tax").unwrap();
+        let net_cf = f64_values(&df, "net_cf").unwrap();
+        let pre_tax_profit: Vec<f64> = ["expenses", "claims", "commissions"].iter().fold(
+            premiums
+                .iter()
+                .zip(premium_tax.iter())
+                .map(|(p, pt)| p - pt)
+                .collect::<Vec<f64>>(),
+            |acc, name| {
+                acc.iter()
+                    .zip(f64_values(&df, name).unwrap())
+                    .map(|(a, v)| a - v)
+                    .collect()
+            },
+        );
+
+        let mut loss_carried_forward = 0.0;
+        for k in 0..df.height() {
+            if k > 0 && id.get(k) != id.get(k - 1) {
+                loss_carried_forward = 0.0;
+            }
+
+            assert!((premium_tax[k] - 0.02 * premiums[k]).abs() < 1e-9);
+            assert!((tax[k] - 0.25 * taxable_profit[k]).abs() < 1e-9);
+            assert!((net_cf[k] - (pre_tax_profit[k] - tax[k])).abs() < 1e-6);
+
+            // No tax until the losses carried forward are recouped
+            let expected = (pre_tax_profit[k] - loss_carried_forward).max(0.0);
+            assert!((taxable_profit[k] - expected).abs() < 1e-6);
+            loss_carried_forward = (loss_carried_forward - pre_tax_profit[k]).max(0.0);
+        }
+
+        // Commission strain in the first month is a loss carried forward
+        assert!(pre_tax_profit[0] < 0.0);
+        assert_eq!(taxable_profit[0], 0.0);
+        assert!(tax.iter().any(|&tax| tax > 0.0));
+    }
+
+    #[test]
+    fn test_fn_projection_block_surplus() {
+        let model_points = _model_points();
+        let assumptions = AssumptionScenario::new_by_name("pricing").unwrap();
+
+        let df = ProjectionBlock::project(&model_points, &assumptions).unwrap();
+        let earned_df =
+            ProjectionBlock::project(&model_points, &assumptions.clone().with_earned_rate(0.1))
+                .unwrap();
+
+        println!("{earned_df:?}");
+
+        let totals = |df: &DataFrame| -> DataFrame {
+            df.clone()
+                .lazy()
+                .group_by_stable([col("id")])
+                .agg([
+                    col("net_cf").sum(),
+                    col("investment_income").sum(),
+                    col("statutory_profit").sum(),
+                    col("assets_bop").first(),
+                ])
+                .collect()
+                .unwrap()
+        };
+
+        for df in [&df, &earned_df] {
+            let totals_df = totals(df);
+
+            // No assets before issue, and the reserve releases add up to nil over the term
+            let net_cf = f64_values(&totals_df, "net_cf").unwrap();
+            let investment_income = f64_values(&totals_df, "investment_income").unwrap();
+            let statutory_profit = f64_values(&totals_df, "statutory_profit").unwrap();
+            for i in 0..totals_df.height() {
+                assert_eq!(f64_values(&totals_df, "assets_bop").unwrap()[i], 0.0);
+                let expected = net_cf[i] + investment_income[i];
+                assert!((statutory_profit[i] - expected).abs() < 1e-6 * (1.0 + expected.abs()));
+            }
+        }
+
+        // Assets earn more above the spot curve
+        let investment_income = |df: &DataFrame| -> f64 {
+            df.column("investment_income")
+                .unwrap()
+                .f64()
+                .unwrap()
+                .sum()
+                .unwrap()
+        };
+        assert!(investment_income(&earned_df) > investment_income(&df));
+    }
+
+    #[test]
+    fn test_fn_projection_block_pricing_basis_and_mass_lapse() {
+        let model_points = _model_points();
+        let assumptions = AssumptionScenario::new_by_name("pricing").unwrap();
+        let shocked = assumptions
+            .clone()
+            .with_mass_lapse(0.4)
+            .with_pricing_basis(assumptions.clone());
+
+        let df = ProjectionBlock::project(&model_points, &assumptions).unwrap();
+        let shocked_df = ProjectionBlock::project(&model_points, &shocked).unwrap();
+
+        println!("{shocked_df:?}");
+
+        // Premiums are unchanged, 40% of the policies lapse in the first month
+        assert!(
+            df.column("prem_pp")
+                .unwrap()
+                .equals(shocked_df.column("prem_pp").unwrap())
+        );
+        let pols_lapse = shocked_df.column("pols_lapse").unwrap().f64().unwrap();
+        assert!(pols_lapse.get(0).unwrap() > 0.4 * model_points[0].policy_count);
+        assert!(
+            shocked_df
+                .column("pols_if")
+                .unwrap()
+                .f64()
+                .unwrap()
+                .get(1)
+                .unwrap()
+                < 0.6 * model_points[0].policy_count
+        );
+    }
+
+    #[test]
+    fn test_fn_projection_block_valuation_date() {
+        let model_points = _model_points();
+        let assumptions = AssumptionScenario::new_by_name("pricing").unwrap();
+        let valuation_date = NaiveDate::from_ymd_opt(2024, 11, 1).unwrap();
+
+        let options = ProjectionOptions {
+            valuation_date: Some(valuation_date),
+            ..Default::default()
+        };
+
+        let df = ProjectionBlock::project(&model_points, &assumptions).unwrap();
+        let dated_df =
+            ProjectionBlock::project_with_options(&model_points, &assumptions, &options).unwrap();
+
+        println!("{dated_df:?}");
+
+        // Calendar months from the valuation date
+        let date = dated_df.column("date").unwrap();
+        let days = |date: NaiveDate| {
+            AnyValue::Date((date - NaiveDate::from_ymd_opt(1970, 1, 1).unwrap()).num_days() as i32)
+        };
+        assert_eq!(date.get(0).unwrap(), days(valuation_date));
+        assert_eq!(
+            date.get(14).unwrap(),
+            days(NaiveDate::from_ymd_opt(2026, 1, 1).unwrap())
+        );
+
+        // Spot rates move to the second year in January rather than at t = 12
+        let spot_rate = |df: &DataFrame, t: usize| {
+            df.column("spot_rate")
+                .unwrap()
+                .f64()
+                .unwrap()
+                .get(t)
+                .unwrap()
+        };
+        assert_eq!(spot_rate(&dated_df, 1), spot_rate(&df, 0));
+        assert_eq!(spot_rate(&dated_df, 2), spot_rate(&df, 12));
+        assert_eq!(spot_rate(&dated_df, 14), spot_rate(&df, 24));
+    }
+
+    #[test]
+    fn test_fn_projection_block_output_columns() {
+        let model_points = _model_points();
+        let valuation_date = NaiveDate::from_ymd_opt(2024, 11, 1).unwrap();
+        let assumptions = AssumptionScenario::new_by_name("pricing").unwrap();
+        let options = ProjectionOptions {
+            columns: Some(vec![
+                "net_cf".to_string(),
+                "date".to_string(),
+                "id".to_string(),
+                "t".to_string(),
+            ]),
+            valuation_date: Some(valuation_date),
+        };
+        let dated = ProjectionOptions {
+            valuation_date: Some(valuation_date),
+            ..Default::default()
+        };
+
+        let df =
+            ProjectionBlock::project_with_options(&model_points, &assumptions, &dated).unwrap();
+        let selected_df =
+            ProjectionBlock::project_with_options(&model_points, &assumptions, &options).unwrap();
+
+        println!("{selected_df:?}");
+
+        // Only the requested columns are flattened, in the engine's order
+        assert_eq!(
+            selected_df.get_column_names(),
+            vec!["id", "t", "date", "net_cf"]
+        );
+        assert!(selected_df.equals(&df.select(["id", "t", "date", "net_cf"]).unwrap()));
+    }
+
+    #[test]
+    fn test_fn_projection_block_decrement_options() {
+        let model_points = _model_points();
+        let project = |conversion: DecrementConversion, order: DecrementOrder| {
+            let assumptions = AssumptionScenario::new_by_name("pricing")
+                .unwrap()
+                .with_decrement_conversion(conversion)
+                .with_decrement_order(order);
+            ProjectionBlock::project(&model_points, &assumptions).unwrap()
+        };
+
+        // Defaults are unchanged
+        let assumptions = AssumptionScenario::new_by_name("pricing").unwrap();
+        let default_df = ProjectionBlock::project(&model_points, &assumptions).unwrap();
+        assert!(default_df.equals(&project(
+            DecrementConversion::ConstantForce,
+            DecrementOrder::DeathFirst
+        )));
+
+        // Straight division by 12
+        let divide12_df = project(DecrementConversion::Divide12, DecrementOrder::DeathFirst);
+        for (q, q_mth) in f64_values(&divide12_df, "mort_rate")
+            .unwrap()
+            .iter()
+            .zip(f64_values(&divide12_df, "mort_rate_mth").unwrap())
+        {
+            assert!((q / 12.0 - q_mth).abs() < 1e-15);
+        }
+
+        // UDD monthly rates compound back to the annual rate over the first policy year
+        let udd_df = project(DecrementConversion::Udd, DecrementOrder::DeathFirst);
+        let q = f64_values(&udd_df, "mort_rate").unwrap()[0];
+        let survival: f64 = f64_values(&udd_df, "mort_rate_mth").unwrap()[..12]
+            .iter()
+            .map(|q_mth| 1.0 - q_mth)
+            .product();
+        assert!((survival - (1.0 - q)).abs() < 1e-12);
+
+        // Ordering shifts exits between death and lapse but not the total
+        let lapse_first_df = project(
+            DecrementConversion::ConstantForce,
+            DecrementOrder::LapseFirst,
+        );
+        let mid_month_df = project(DecrementConversion::ConstantForce, DecrementOrder::MidMonth);
+        let death_first = f64_values(&default_df, "pols_death").unwrap();
+        let lapse_first = f64_values(&lapse_first_df, "pols_death").unwrap();
+        let mid_month = f64_values(&mid_month_df, "pols_death").unwrap();
+        assert!(lapse_first[0] < mid_month[0] && mid_month[0] < death_first[0]);
+
+        let pols_if = f64_values(&default_df, "pols_if").unwrap();
+        for df in [&lapse_first_df, &mid_month_df] {
+            for (a, b) in pols_if.iter().zip(f64_values(df, "pols_if").unwrap()) {
+                assert!((a - b).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fn_projection_block_matches_single_model_point() {
+        let model_points = _model_points();
+        let assumptions = AssumptionScenario::new_by_name("pricing").unwrap();
+
+        let block_df = ProjectionBlock::project(&model_points, &assumptions).unwrap();
+
+        let mut single_df = DataFrame::empty();
+        for mp in model_points.iter() {
+            let mp_df = ProjectionBlock::project(std::slice::from_ref(mp), &assumptions).unwrap();
+            single_df.vstack_mut(&mp_df).unwrap();
+        }
+
+        assert!(block_df.equals(&single_df));
+    }
+
+    #[test]
+    fn test_fn_projection_block_matches_lazy_frame_engine() {
+        let df = df![
+            "model" => ["s_model"; 3],
+            "id" => [28, 32, 40],
+            "entry_age" => [41, 56, 46],
+            "gender" => ["M", "F", "M"],
+            "term" => [15, 15, 10],
+            "policy_count" => [1.0; 3],
+            "sum_insured" => [202_000.0, 567_000.0, 405_000.0],
+        ]
+        .unwrap();
+        let model_points = SModelPoint::from_df(&df).unwrap();
+        let assumptions = AssumptionScenario::new_by_name("pricing").unwrap();
+
+        let block_df = ProjectionBlock::project(&model_points, &assumptions).unwrap();
+
+        // Captured from the LazyFrame S model projection - premium per policy, then by id and t:
+        // pols_if, claims, net_cf
+        let prem_pp = [
+            (28, 128.87133809669734),
+            (32, 777.3104991406715),
+            (40, 328.2462170103819),
+        ];
+        let expected = [
+            (28, 0, 1.0, 57.66043864456516, -417.66043864456515),
+            (28, 12, 0.597948, 37.406498500326876, 3.0574427719171027),
+            (
+                28,
+                60,
+                0.16050810988773032,
+                13.866228538635955,
+                -3.814169015129959,
+            ),
+            (
+                28,
+                119,
+                0.10894719307482434,
+                12.878983396624546,
+                -6.794035372349067,
+            ),
+            (32, 0, 1.0, 369.87416969482746, -729.8741696948275),
+            (
+                32,
+                12,
+                0.5953200000000007,
+                232.94531949149638,
+                193.36958285692867,
+            ),
+            (
+                32,
+                60,
+                0.15675787058969917,
+                78.44746255955079,
+                33.01767473264127,
+            ),
+            (
+                32,
+                119,
+                0.10290466914005748,
+                74.86987700917504,
+                -2.3950003374406776,
+            ),
+            (40, 0, 1.0, 173.20683542067505, -533.206835420675),
+            (40, 12, 0.596928, 111.69252706336863, 47.714837164204624),
+            (
+                40,
+                60,
+                0.15893164585767997,
+                41.07071178121772,
+                0.5695969820527438,
+            ),
+            (
+                40,
+                119,
+                0.10618904719998866,
+                39.478034487519245,
+                -12.375706743404123,
+            ),
+        ];
+
+        for (id, t, pols_if, claims, net_cf) in expected {
+            let row_df = block_df
+                .clone()
+                .lazy()
+                .filter(col("id").eq(lit(id)).and(col("t").eq(lit(t as f64))))
+                .collect()
+                .unwrap();
+            let (_, prem_pp) = prem_pp.into_iter().find(|(mp_id, _)| *mp_id == id).unwrap();
+            for (name, value) in [
+                ("prem_pp", prem_pp),
+                ("pols_if", pols_if),
+                ("claims", claims),
+                ("net_cf", net_cf),
+            ] {
+                let actual = row_df.column(name).unwrap().f64().unwrap().get(0).unwrap();
+                assert!(
+                    (actual - value).abs() < 1e-9 * (1.0 + value.abs()),
+                    "id {id}, t {t}, {name}: {actual} vs {value}"
+                );
+            }
+        }
+    }
+}
@@ -1,8 +1,11 @@
+use super::projection_block::ProjectionBlock;
 use super::*;
 
 //---------------------------------------------------------------------------------------------------------
 // STRUCTS
 //---------------------------------------------------------------------------------------------------------
+#[allow(dead_code)]
+#[derive(Clone, Debug)]
 pub struct SModelPoint {
     pub model: String,
     pub id: i32,
@@ -11,303 +14,179 @@ pub struct SModelPoint {
     pub term: i32,
     pub policy_count: f64,
     pub sum_insured: f64,
+    pub channel: Option<String>, // Optional `channel` column - used by commission scales
+    pub payment_freq: i32,       // Optional `payment_freq` column - monthly premiums by default
+    pub benefit_pattern: BenefitPattern, // Optional `benefit_pattern` and `benefit_rate` columns
 }
 
-impl SModelPoint {
-    pub fn project(&self, assumptions: &AssumptionScenario) -> PolarsResult<LazyFrame> {
-        // Initialize projection dataframe - using all interger values
-        let lf = _initialize_lf(self.id, self.term, self.entry_age, self.sum_insured)?;
-
-        // Map assumptions
-        let lf = _map_assumptions(lf, assumptions, &self.gender)?;
-
-        // Perform projection
-        let lf = _discount_factor(lf)?;
-        let lf = _exp_pp(lf)?;
-        let lf = _policies_movement(lf, self.policy_count, self.term)?;
-        let lf = _complete_projection(lf)?;
-
-        Ok(lf)
+impl ProductModel for SModelPoint {
+    fn name() -> &'static str {
+        "s_model"
     }
-}
-
-//---------------------------------------------------------------------------------------------------------
-// PRIVATE
-//---------------------------------------------------------------------------------------------------------
-// ------------------Intialize LazyFrame------------------
-fn _initialize_lf(id: i32, term: i32, entry_age: i32, sum_insured: f64) -> PolarsResult<LazyFrame> {
-    let length = (term * 12 + 1) as usize; // Total months in the term
-
-    let lf = df![
-        "id" => vec![id; length],
-        "term" => vec![term; length],
-        "sum_insured" => vec![sum_insured; length],
-        "claim_pp" => vec![sum_insured; length], // Claim per policy is the sum insured
-        "t" => (0..= (length - 1) as i32).map(|x| x as f64).collect::<Vec<f64>>(),
-        "t_i32" => (0..= (length -1) as i32).collect::<Vec<i32>>(), // t_ is used for duration
-    ]
-    .unwrap()
-    .lazy()
-    .with_column((col("t_i32") / lit(12)).alias("duration"))
-    .with_column((lit(entry_age) + col("duration")).alias("age"))
-    .select([all().exclude(["t_i32"])]); // Drop t_i32 column
-
-    Ok(lf)
-}
-
-// ------------------Map assumptions------------------
-// Map mortality assumption
-fn __map_mort_assumption(
-    lf: LazyFrame,
-    mort_df: &DataFrame,
-    gender: &str,
-) -> PolarsResult<LazyFrame> {
-    // Find the column name according to gender
-    let suffix = format!("_{}", gender.to_lowercase());
-    let mort_col_name = mort_df
-        .get_column_names()
-        .iter()
-        .find(|&col| col.ends_with(&suffix))
-        .ok_or_else(|| {
-            PolarsError::ComputeError(
-                format!("Mortality column with suffix '{suffix}' not found").into(),
-            )
-        })?
-        .as_str();
-
-    // Convert to LazyFrame, selecting columns and renmaing the mortality rate column
-    let mort_lf = mort_df
-        .clone()
-        .lazy()
-        .select([col("age"), col(mort_col_name).alias("mort_rate")]);
-
-    // Left join with the mortality rate - similar to vlookup in Excel
-    let lf = lf
-        .left_join(mort_lf, col("age"), col("age"))
-        .with_column(col("mort_rate").fill_null(lit(0.0)).alias("mort_rate"));
-
-    Ok(lf)
-}
 
-// Map lapse, Inflation, Expenses and Spot rate assumption
-fn __map_other_assumption(lf: LazyFrame, lookup_df: &DataFrame) -> PolarsResult<LazyFrame> {
-    let col_name = lookup_df.get_column_names()[1].as_str();
-
-    let lookup_lf = lookup_df
-        .clone()
-        .lazy()
-        .with_column((col("year") - lit(1)).alias("duration")) // Adjust year to duration
-        .select([col("duration"), col(col_name)]); // Drop "year" column
-
-    let lf = lf
-        .left_join(lookup_lf, col("duration"), col("duration"))
-        .with_column(col(col_name).fill_null(lit(0.0)).alias(col_name)); // Fill null with 0.0
-
-    Ok(lf)
-}
-
-fn _map_assumptions(
-    lf: LazyFrame,
-    assumptions: &AssumptionScenario,
-    gender: &str,
-) -> PolarsResult<LazyFrame> {
-    // Map mortality assumption based
-    let lf = __map_mort_assumption(lf, &assumptions.mort, gender)?;
-
-    // Map other assumptions by iterating over each field of the AssumptionSet struct
-    let lf = __map_other_assumption(lf, &assumptions.lapse)?;
-    let lf = __map_other_assumption(lf, &assumptions.acq)?;
-    let lf = __map_other_assumption(lf, &assumptions.mtn)?;
-    let lf = __map_other_assumption(lf, &assumptions.inf)?;
-    let lf = __map_other_assumption(lf, &assumptions.spot)?;
-    let lf = __map_other_assumption(lf, &assumptions.load)?;
-
-    Ok(lf)
-}
-
-// ------------------Discount factor------------------
-fn _discount_factor(lf: LazyFrame) -> PolarsResult<LazyFrame> {
-    let lf = lf
-        .with_column(
-            // Spot rate monthly
-            ((lit(1.0) + col("spot_rate")).pow(1.0 / 12.0) - lit(1.0)).alias("spot_rate_mth"),
-        )
-        .with_column(
-            // Discount factor
-            (lit(1.0) / (lit(1.0) + col("spot_rate_mth")).pow(col("t"))).alias("discount_factor"),
-        );
-
-    Ok(lf)
-}
+    fn schema() -> Vec<(&'static str, DataType)> {
+        vec![
+            ("model", DataType::String),
+            ("id", DataType::Int32),
+            ("entry_age", DataType::Int32),
+            ("gender", DataType::String),
+            ("term", DataType::Int32),
+            ("policy_count", DataType::Float64),
+            ("sum_insured", DataType::Float64),
+        ]
+    }
 
-// ------------------Inflation factor------------------
-fn _inflation_factor(lf: LazyFrame) -> PolarsResult<LazyFrame> {
-    let lf = lf.with_column(
-        // Inflation factor - for flat curve only
-        (lit(1.0) + col("inf_rate"))
-            .pow(col("t") / lit(12.0))
-            .alias("inf_factor"),
-    );
+    fn from_df(df: &DataFrame) -> PolarsResult<Vec<Self>> {
+        let model = _str_col(df, "model")?;
+        let id = _i32_col(df, "id")?;
+        let entry_age = _i32_col(df, "entry_age")?;
+        let gender = _str_col(df, "gender")?;
+        let term = _i32_col(df, "term")?;
+        let policy_count = _f64_col(df, "policy_count")?;
+        let sum_insured = _f64_col(df, "sum_insured")?;
+        let channel = _opt_str_col(df, "channel")?;
+        let payment_freq = _opt_i32_col(df, "payment_freq", 12)?;
+        let benefit_pattern = _opt_str_col(df, "benefit_pattern")?;
+        let benefit_rate = _opt_f64_col(df, "benefit_rate", 0.0)?;
+
+        (0..df.height())
+            .map(|i| {
+                Ok(SModelPoint {
+                    model: model[i].clone(),
+                    id: id[i],
+                    entry_age: entry_age[i],
+                    gender: gender[i].clone(),
+                    term: term[i],
+                    policy_count: policy_count[i],
+                    sum_insured: sum_insured[i],
+                    channel: channel[i].clone(),
+                    payment_freq: payment_freq[i],
+                    benefit_pattern: BenefitPattern::new(
+                        benefit_pattern[i].as_deref(),
+                        benefit_rate[i],
+                    )?,
+                })
+            })
+            .collect()
+    }
 
-    Ok(lf)
-}
+    fn project(&self, assumptions: &AssumptionScenario) -> PolarsResult<LazyFrame> {
+        // A single model point is a block of one
+        let df = ProjectionBlock::project(std::slice::from_ref(self), assumptions)?;
+        Ok(df.lazy())
+    }
 
-// ------------------Expense per policy------------------
-fn _exp_pp(lf: LazyFrame) -> PolarsResult<LazyFrame> {
-    let lf = lf
-        .with_columns(vec![
-            // Total real expense per policy
-            (col("real_acq_exp_pp") + col("real_mtn_exp_pp")).alias("real_exp_pp"),
-            // Inflation factor - for flat curve only
-            (lit(1.0) + col("inf_rate"))
-                .pow(col("t") / lit(12.0))
-                .alias("inf_factor"),
-        ])
-        .with_column(
-            // Adjusted expense per policy
-            (col("real_exp_pp") * col("inf_factor")).alias("exp_pp"),
-        );
+    fn project_block(
+        model_points: &[Self],
+        assumptions: &AssumptionScenario,
+    ) -> PolarsResult<DataFrame> {
+        ProjectionBlock::project(model_points, assumptions)
+    }
 
-    Ok(lf)
+    fn project_block_with_options(
+        model_points: &[Self],
+        assumptions: &AssumptionScenario,
+        options: &ProjectionOptions,
+    ) -> PolarsResult<DataFrame> {
+        ProjectionBlock::project_with_options(model_points, assumptions, options)
+    }
 }
 
-// ------------------Policy movement------------------
-fn _policies_movement(lf: LazyFrame, policy_count: f64, term: i32) -> PolarsResult<LazyFrame> {
-    let lf = lf.with_columns(vec![
-        // Monthly decrement rate
-        (lit(1.0) - (lit(1.0) - col("mort_rate")).pow(1.0 / 12.0)).alias("mort_rate_mth"),
-        (lit(1.0) - (lit(1.0) - col("lapse_rate")).pow(1.0 / 12.0)).alias("lapse_rate_mth"),
-    ]);
-
-    let df = lf.clone().collect()?;
-
-    // Height of the dataframe
-    let height = df.height() as usize;
-
-    // Monthly mortality and lapse rate
-    let mort_rate_mth = df.column("mort_rate_mth")?.f64()?.to_vec();
-    let lapse_rate_mth = df.column("lapse_rate_mth")?.f64()?.to_vec();
-
-    // Create a vector of 0.0 with length equal to lf.height()
-    let mut pols_if = Array1::<f64>::zeros(height).to_vec();
-    pols_if[0] = policy_count; // Set first element to policy_count
-
-    let mut pols_maturity = Array1::<f64>::zeros(height).to_vec();
-    let mut pols_death = Array1::<f64>::zeros(height).to_vec();
-    let mut pols_lapse = Array1::<f64>::zeros(height).to_vec();
-
-    for i in 0..(height - 1) {
-        if i == 0 {
-            pols_if[i] = policy_count;
-        } else {
-            pols_if[i] =
-                pols_if[i - 1] - pols_maturity[i - 1] - pols_death[i - 1] - pols_lapse[i - 1];
-        }
-
-        pols_maturity[i] = if i == (term * 12) as usize {
-            pols_if[i] // Maturity at the end of the term
-        } else {
-            0.0 // No maturity before term ends
+//---------------------------------------------------------------------------------------------------------
+// UNIT TESTS
+//---------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp_gen::pricing_mp_gen::PricingGrid;
+
+    #[test]
+    fn test_fn_s_model_payment_freq() {
+        let df = PricingGrid::new("s_model")
+            .with_ages(&[40])
+            .with_terms(&[10])
+            .with_genders(&["M"])
+            .with_sum_insureds(&[100_000.0])
+            .with_dimension("payment_freq", &["1", "12"])
+            .generate()
+            .unwrap();
+        let assumptions = AssumptionScenario::new_by_name("pricing")
+            .unwrap()
+            .with_modal_loading(12, 0.04);
+
+        let model_points = SModelPoint::from_df(&df).unwrap();
+        let projected_df = SModelPoint::project_block(&model_points, &assumptions).unwrap();
+
+        println!("{projected_df:?}");
+
+        let installments = |id: i32| -> Vec<(f64, f64)> {
+            let mp_df = projected_df
+                .clone()
+                .lazy()
+                .filter(col("id").eq(lit(id)).and(col("premiums").gt(lit(0.0))))
+                .collect()
+                .unwrap();
+            let t = mp_df.column("t").unwrap().f64().unwrap();
+            let prem_pp = mp_df.column("prem_pp").unwrap().f64().unwrap();
+            t.into_no_null_iter()
+                .zip(prem_pp.into_no_null_iter())
+                .collect()
         };
 
-        pols_death[i] = (pols_if[i] - pols_maturity[i]) * mort_rate_mth[i].unwrap_or(0.0);
-        pols_lapse[i] =
-            (pols_if[i] - pols_maturity[i] - pols_death[i]) * lapse_rate_mth[i].unwrap_or(0.0);
+        // Annual premiums at the start of each policy year, monthly premiums every month
+        let annual = installments(1);
+        let monthly = installments(2);
+        assert_eq!(model_points[0].payment_freq, 1);
+        assert_eq!(
+            annual.iter().map(|(t, _)| *t).collect::<Vec<f64>>(),
+            (0..10).map(|y| (y * 12) as f64).collect::<Vec<f64>>()
+        );
+        assert_eq!(monthly.len(), 120);
+
+        // Monthly installments carry the modal loading and lose premium on lapses within the year,
+        // so cost more than an annual premium over the year
+        let annual_prem = annual[0].1;
+        let monthly_prem = monthly[0].1;
+        assert!(12.0 * monthly_prem > annual_prem);
+        assert!(12.0 * monthly_prem < 1.3 * annual_prem);
     }
 
-    // Create a DataFrame from these vectors
-    let new_df = df![
-        "pols_if" => pols_if,
-        "pols_maturity" => pols_maturity,
-        "pols_death" => pols_death,
-        "pols_lapse" => pols_lapse,
-    ]?;
-
-    // Horizontally concatenate the new columns to the existing LazyFrame
-    let lf = df
-        .hstack(&[
-            new_df.column("pols_if")?.clone(),
-            new_df.column("pols_maturity")?.clone(),
-            new_df.column("pols_death")?.clone(),
-            new_df.column("pols_lapse")?.clone(),
-        ])?
-        .lazy();
-
-    Ok(lf)
-}
-
-// ------------------Complete projection------------------
-fn __calculate_net_premium(lf: LazyFrame) -> PolarsResult<f64> {
-    // Calculate both PV claims and premium annuities in a single operation
-    let lf = lf
-        .with_column(
-            // Portfolio claims
-            (col("claim_pp") * col("pols_death")).alias("claims"),
-        )
-        .with_columns(vec![
-            // PV of claims and PV of premium annuities
-            (col("claims") * col("discount_factor")).alias("pv_claims_component"),
-            (col("pols_if") * col("discount_factor")).alias("pv_annuities_component"),
-        ])
-        .select([
-            col("pv_claims_component").sum().alias("pv_claims"),
-            col("pv_annuities_component").sum().alias("prem_annuities"),
-        ])
-        .collect()?;
-
-    // Extract both values in one operation
-    let pv_claims = lf
-        .column("pv_claims")?
-        .get(0)?
-        .extract::<f64>()
-        .unwrap_or(0.0);
-
-    let prem_annuities = lf
-        .column("prem_annuities")?
-        .get(0)?
-        .extract::<f64>()
-        .unwrap_or(0.0);
-
-    // Calculate net premium
-    let net_premium = if prem_annuities != 0.0 {
-        pv_claims / prem_annuities
-    } else {
-        0.0
-    };
-
-    Ok(net_premium)
-}
-
-// ------------------Complete projection------------------
-fn _complete_projection(lf: LazyFrame) -> PolarsResult<LazyFrame> {
-    // Calculate net premium
-    let net_prem = __calculate_net_premium(lf.clone())?;
+    #[test]
+    fn test_fn_s_model_benefit_pattern() {
+        let df = PricingGrid::new("s_model")
+            .with_ages(&[40])
+            .with_terms(&[10])
+            .with_genders(&["M"])
+            .with_sum_insureds(&[100_000.0])
+            .with_dimension("benefit_pattern", &["level", "decreasing", "cpi"])
+            .generate()
+            .unwrap();
+        let assumptions = AssumptionScenario::new_by_name("pricing").unwrap();
+
+        let model_points = SModelPoint::from_df(&df).unwrap();
+        let projected_df = SModelPoint::project_block(&model_points, &assumptions).unwrap();
+
+        println!("{projected_df:?}");
+
+        let value = |id: i32, t: f64, name: &str| -> f64 {
+            let row = projected_df
+                .clone()
+                .lazy()
+                .filter(col("id").eq(lit(id)).and(col("t").eq(lit(t))))
+                .collect()
+                .unwrap();
+            row.column(name).unwrap().f64().unwrap().get(0).unwrap()
+        };
 
-    // Add net premium to the lazyframe
-    let lf = lf
-        .with_columns(vec![
-            // Loaded premium and round to 2 decimal places
-            ((lit(1.0) + col("load_rate")) * lit(net_prem)).alias("prem_pp"),
-            // Portfolio expense
-            (col("exp_pp") * col("pols_if")).alias("expenses"),
-        ])
-        .with_columns(vec![
-            // Portfolio claims
-            (col("claim_pp") * col("pols_death")).alias("claims"),
-            // Portfolio premiums
-            (col("prem_pp") * col("pols_if")).alias("premiums"),
-        ])
-        .with_column(
-            // Features is simple - Comission is 100% of premium in the first year
-            when(col("duration").eq(0))
-                .then(col("premiums"))
-                .otherwise(lit(0.0))
-                .alias("commissions"),
-        )
-        .with_column(
-            (col("premiums") - col("expenses") - col("claims") - col("commissions"))
-                .alias("net_cf"),
-        );
+        // Level cover throughout, decreasing cover halved by the middle of the term
+        assert_eq!(value(1, 60.0, "claim_pp"), 100_000.0);
+        assert_eq!(value(2, 60.0, "claim_pp"), 50_000.0);
+        assert!(value(2, 0.0, "prem_pp") < value(1, 0.0, "prem_pp"));
 
-    Ok(lf)
+        // CPI linked cover increases with inflation at the first anniversary
+        let inf_rate = value(3, 0.0, "inf_rate");
+        assert_eq!(value(3, 11.0, "claim_pp"), 100_000.0);
+        assert!((value(3, 12.0, "claim_pp") - 100_000.0 * (1.0 + inf_rate)).abs() < 1e-9);
+        assert!(value(3, 0.0, "prem_pp") > value(1, 0.0, "prem_pp"));
+    }
 }
@@ -1,8 +1,11 @@
+use super::projection_block::{PolicyBasis, ProjectionBlock};
 use super::*;
 
 //---------------------------------------------------------------------------------------------------------
 // STRUCT
 //---------------------------------------------------------------------------------------------------------
+#[allow(dead_code)]
+#[derive(Clone, Debug)]
 pub struct SEModelPoint {
     pub model: String,
     pub id: i32,
@@ -12,14 +15,189 @@ pub struct SEModelPoint {
     pub policy_count: f64,
     pub sum_insured: f64,
     pub duration_mth: i32,
+    pub channel: Option<String>,
+    pub payment_freq: i32,
+    pub benefit_pattern: BenefitPattern,
+}
+
+impl ProductModel for SEModelPoint {
+    fn name() -> &'static str {
+        "se_model"
+    }
+
+    fn schema() -> Vec<(&'static str, DataType)> {
+        let mut schema = SModelPoint::schema();
+        schema.push(("duration_mth", DataType::Int32));
+        schema
+    }
+
+    fn from_df(df: &DataFrame) -> PolarsResult<Vec<Self>> {
+        let s_model_points = SModelPoint::from_df(df)?;
+        let duration_mth = _i32_col(df, "duration_mth")?;
+
+        let model_points = s_model_points
+            .into_iter()
+            .zip(duration_mth)
+            .map(|(mp, duration_mth)| SEModelPoint {
+                model: mp.model,
+                id: mp.id,
+                entry_age: mp.entry_age,
+                gender: mp.gender,
+                term: mp.term,
+                policy_count: mp.policy_count,
+                sum_insured: mp.sum_insured,
+                duration_mth,
+                channel: mp.channel,
+                payment_freq: mp.payment_freq,
+                benefit_pattern: mp.benefit_pattern,
+            })
+            .collect();
+
+        Ok(model_points)
+    }
+
+    fn project(&self, assumptions: &AssumptionScenario) -> PolarsResult<LazyFrame> {
+        let df = Self::project_block(std::slice::from_ref(self), assumptions)?;
+        Ok(df.lazy())
+    }
+
+    // Same engine as the S model, starting from the duration at the valuation date
+    fn project_block(
+        model_points: &[Self],
+        assumptions: &AssumptionScenario,
+    ) -> PolarsResult<DataFrame> {
+        Self::project_block_with_options(model_points, assumptions, &ProjectionOptions::default())
+    }
+
+    fn project_block_with_options(
+        model_points: &[Self],
+        assumptions: &AssumptionScenario,
+        options: &ProjectionOptions,
+    ) -> PolarsResult<DataFrame> {
+        let s_model_points: Vec<SModelPoint> =
+            model_points.iter().map(|mp| mp.s_model_point()).collect();
+        let bases: Vec<PolicyBasis> = model_points
+            .iter()
+            .map(|mp| PolicyBasis {
+                duration_mth: mp.duration_mth,
+                ..PolicyBasis::new_business(&mp.s_model_point())
+            })
+            .collect();
+
+        ProjectionBlock::project_in_force(&s_model_points, &bases, assumptions, options)
+    }
 }
 
 impl SEModelPoint {
-    pub fn project(&self, assumptions: &AssumptionScenario) -> PolarsResult<LazyFrame> {
-        todo!("Implement SEModelPoint projection logic here");
+    // Policy attributes at issue
+    pub(super) fn s_model_point(&self) -> SModelPoint {
+        SModelPoint {
+            model: self.model.clone(),
+            id: self.id,
+            entry_age: self.entry_age,
+            gender: self.gender.clone(),
+            term: self.term,
+            policy_count: self.policy_count,
+            sum_insured: self.sum_insured,
+            channel: self.channel.clone(),
+            payment_freq: self.payment_freq,
+            benefit_pattern: self.benefit_pattern,
+        }
     }
 }
 
 //---------------------------------------------------------------------------------------------------------
-// PRIVATE
+// UNIT TESTS
 //---------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mp_gen::pricing_mp_gen::PricingGrid;
+
+    fn _se_model_points_df(duration_mth: i32) -> DataFrame {
+        let mut df = PricingGrid::new("se_model")
+            .with_ages(&[30, 50])
+            .with_terms(&[10, 20])
+            .with_genders(&["M", "F"])
+            .with_sum_insureds(&[100_000.0])
+            .generate()
+            .unwrap();
+        let height = df.height();
+        df.with_column(Series::new(
+            "duration_mth".into(),
+            vec![duration_mth; height],
+        ))
+        .unwrap();
+        df
+    }
+
+    #[test]
+    fn test_fn_se_model_duration_zero_matches_s_model() {
+        let assumptions = AssumptionScenario::new_by_name("pricing").unwrap();
+        let df = _se_model_points_df(0);
+
+        let se_df = SEModelPoint::project_block(&SEModelPoint::from_df(&df).unwrap(), &assumptions)
+            .unwrap();
+        let s_df =
+            SModelPoint::project_block(&SModelPoint::from_df(&df).unwrap(), &assumptions).unwrap();
+
+        assert!(se_df.equals(&s_df));
+    }
+
+    #[test]
+    fn test_fn_se_model_in_force() {
+        let assumptions = AssumptionScenario::new_by_name("pricing").unwrap();
+        let df = _se_model_points_df(30);
+
+        let model_points = SEModelPoint::from_df(&df).unwrap();
+        let se_df = SEModelPoint::project_block(&model_points, &assumptions).unwrap();
+        let s_df =
+            SModelPoint::project_block(&SModelPoint::from_df(&df).unwrap(), &assumptions).unwrap();
+
+        println!("{se_df:?}");
+
+        // Remaining term only
+        let rows: i32 = model_points.iter().map(|mp| mp.term * 12 - 30 + 1).sum();
+        assert_eq!(se_df.height(), rows as usize);
+
+        // First model point: attained age and duration, premium and decrements as the S model 30 months in
+        let se_mp_df = se_df
+            .filter(&se_df.column("id").unwrap().i32().unwrap().equal(1))
+            .unwrap();
+        let s_mp_df = s_df
+            .filter(&s_df.column("id").unwrap().i32().unwrap().equal(1))
+            .unwrap()
+            .slice(30, se_mp_df.height());
+
+        for name in [
+            "duration",
+            "age",
+            "mort_rate_mth",
+            "lapse_rate_mth",
+            "prem_pp",
+            "claim_pp",
+        ] {
+            assert!(
+                se_mp_df
+                    .column(name)
+                    .unwrap()
+                    .equals(s_mp_df.column(name).unwrap()),
+                "{name}"
+            );
+        }
+
+        // In-force policy count at the valuation date
+        assert_eq!(
+            se_mp_df.column("pols_if").unwrap().f64().unwrap().get(0),
+            Some(model_points[0].policy_count)
+        );
+    }
+
+    #[test]
+    fn test_fn_se_model_duration_beyond_term() {
+        let assumptions = AssumptionScenario::new_by_name("pricing").unwrap();
+        let model_points = SEModelPoint::from_df(&_se_model_points_df(121)).unwrap();
+
+        assert!(SEModelPoint::project_block(&model_points, &assumptions).is_err());
+    }
+}
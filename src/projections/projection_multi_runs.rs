@@ -3,6 +3,7 @@ use rayon::prelude::*;
 use std::fs::{read_to_string, write};
 use std::path::Path;
 
+use crate::projections::annual_aggregation::YearBasis;
 use crate::projections::helpers::create_folder;
 use crate::projections::projection_single_run::{SingleRunResult, SingleRunSetup};
 
@@ -54,11 +55,45 @@ impl RunsResult {
     }
 
     pub fn aggregate_projection_df(&self) -> PolarsResult<DataFrame> {
-        // Concatenate all projected DataFrames from the results
+        self._stack_runs(|result| Ok(result.projected_df.clone()))
+    }
+
+    // Portfolio totals of every run, computed while projecting and stacked as the projected DataFrames
+    pub fn portfolio_df(&self) -> PolarsResult<DataFrame> {
+        self._stack_runs(|result| Ok(result.portfolio_df.clone()))
+    }
+
+    // Annual figures of every run, stacked as the projected DataFrames
+    pub fn annual_df(&self, basis: YearBasis, group_columns: &[&str]) -> PolarsResult<DataFrame> {
+        self._stack_runs(|result| result.annual_df(basis, group_columns))
+    }
+
+    // Export the annual figures of each run into its run folder
+    pub fn export_annual_df(
+        &self,
+        folder_path_str: &str,
+        basis: YearBasis,
+        group_columns: &[&str],
+    ) -> PolarsResult<()> {
+        let path = Path::new(&folder_path_str);
+
+        for (i, result) in self.results.iter().enumerate() {
+            let run_path = path.join(format!("run_{i}"));
+            result.export_annual_df(run_path.to_str().unwrap(), basis, group_columns)?;
+        }
+
+        Ok(())
+    }
+
+    fn _stack_runs(
+        &self,
+        run_df: impl Fn(&SingleRunResult) -> PolarsResult<DataFrame>,
+    ) -> PolarsResult<DataFrame> {
+        // Concatenate a DataFrame from each of the results
         let mut all_lfs = Vec::with_capacity(self.results.len());
 
         for (i, result) in self.results.iter().enumerate() {
-            let df = result.projected_df.clone();
+            let df = run_df(result)?;
             // Add run_id and run_setup_description columns, assumption_scenario.name
             let lf = df.lazy().with_columns(vec![
                 lit(i as i32).alias("run_id"),
@@ -99,7 +134,7 @@ impl RunsResult {
         for (i, result) in self.results.iter().enumerate() {
             let run_path = path.join(format!("run_{i}")); // Folder containing each run result seperately
             create_folder(&run_path); // Create the folder for the run
-            result.export(&run_path.to_str().unwrap())?; // Export run setup
+            result.export(run_path.to_str().unwrap())?; // Export run setup
         }
 
         Ok(())
@@ -151,9 +186,8 @@ impl RunsResult {
 // PRIVATE
 //---------------------------------------------------------------------------------------------------------
 /*
-Using the below command to run the code in parallel with limited threads finish run in 90s vs 400s in non parallel mode
-The test is not exhaustive, but it shows that parallel processing can significantly speed up the projection of model points.
-$env:RAYON_NUM_THREADS = 8; $env:RUST_MIN_STACK = 33554432; cargo run
+Runs are projected in parallel, and each run projects its model points in blocks with the vectorised engine.
+The number of threads can still be limited if needed, eg: $env:RAYON_NUM_THREADS = 8; cargo run
 */
 fn _project_runs(
     setups: &Vec<SingleRunSetup>,
@@ -1,5 +1,8 @@
 use crate::assumptions::assumption_scenario::AssumptionScenario;
-use crate::projections::projection_mp::ModelPoint;
+use crate::projections::annual_aggregation::{FLOW_COLUMNS, STOCK_COLUMNS, YearBasis, annual_df};
+use crate::projections::portfolio_aggregation::{combine_portfolio_dfs, portfolio_df};
+use crate::projections::projection_mp::{ProductRegistry, ProjectionOptions};
+use chrono::NaiveDate;
 use polars::prelude::*;
 use rayon::prelude::*;
 use std::fs::{File, read_to_string, write};
@@ -19,10 +22,129 @@ pub struct SingleRunSetup {
     pub description: String, // Optional description for the run
     pub model_points_df: DataFrame,
     pub assumption_scenario: AssumptionScenario,
+    pub product_registry: ProductRegistry, // Products available to the `model` column
+    pub output_profile: OutputProfile,     // Columns kept in the projected DataFrame
+    pub portfolio_columns: Vec<String>,    // Model point attributes splitting the portfolio totals
+    pub valuation_date: Option<NaiveDate>, // Projection months follow the calendar from this date
+}
+
+// Columns of the projected DataFrame - present values are always summarised from the full projection
+#[allow(dead_code)]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum OutputProfile {
+    #[default]
+    Full,
+    Cashflows,            // Policy decrements and cashflows only
+    PvOnly,               // No monthly projection, present values only
+    Columns(Vec<String>), // Custom column list
+}
+
+impl OutputProfile {
+    // Columns to keep out of the available ones - None keeps them all
+    fn columns(&self, available: &[String]) -> Option<Vec<String>> {
+        match self {
+            OutputProfile::Full => None,
+            OutputProfile::Cashflows => Some(
+                CASHFLOW_COLUMNS
+                    .iter()
+                    .filter(|c| available.iter().any(|a| a == *c))
+                    .map(|c| c.to_string())
+                    .collect(),
+            ),
+            OutputProfile::PvOnly => Some(Vec::new()),
+            OutputProfile::Columns(columns) => Some(columns.clone()),
+        }
+    }
+
+    // Saved with the run setup - a custom profile as its column list
+    fn to_json(&self) -> serde_json::Value {
+        match self {
+            OutputProfile::Full => "full".into(),
+            OutputProfile::Cashflows => "cashflows".into(),
+            OutputProfile::PvOnly => "pv_only".into(),
+            OutputProfile::Columns(columns) => columns.clone().into(),
+        }
+    }
+
+    fn from_json(value: &serde_json::Value) -> PolarsResult<Self> {
+        let error = || PolarsError::ComputeError(format!("Invalid output profile: {value}").into());
+
+        match value {
+            serde_json::Value::Array(columns) => columns
+                .iter()
+                .map(|c| c.as_str().map(String::from).ok_or_else(error))
+                .collect::<PolarsResult<Vec<String>>>()
+                .map(OutputProfile::Columns),
+            _ => match value.as_str() {
+                Some("full") => Ok(OutputProfile::Full),
+                Some("cashflows") => Ok(OutputProfile::Cashflows),
+                Some("pv_only") => Ok(OutputProfile::PvOnly),
+                _ => Err(error()),
+            },
+        }
+    }
+
+    // Columns the engine projects - the profile's plus those present values and portfolio totals need
+    fn projection_columns(&self, portfolio_columns: &[String]) -> Option<Vec<String>> {
+        let mut columns = self.columns(&CASHFLOW_COLUMNS.map(String::from))?;
+
+        let required = ["id", "t", "discount_factor"]
+            .into_iter()
+            .chain(PV_ITEMS.iter().map(|(name, _)| *name))
+            .chain(FLOW_COLUMNS)
+            .chain(STOCK_COLUMNS)
+            .map(String::from)
+            .chain(portfolio_columns.iter().cloned());
+        for name in required {
+            if !columns.contains(&name) {
+                columns.push(name);
+            }
+        }
+
+        Some(columns)
+    }
 }
 
 #[allow(dead_code)]
 impl SingleRunSetup {
+    pub fn new(
+        description: &str,
+        model_points_df: DataFrame,
+        assumption_scenario: AssumptionScenario,
+    ) -> Self {
+        Self {
+            description: description.to_string(),
+            model_points_df,
+            assumption_scenario,
+            product_registry: ProductRegistry::default(),
+            output_profile: OutputProfile::default(),
+            portfolio_columns: Vec::new(),
+            valuation_date: None,
+        }
+    }
+
+    pub fn with_output_profile(mut self, output_profile: OutputProfile) -> Self {
+        self.output_profile = output_profile;
+        self
+    }
+
+    // Split the portfolio totals by model point attributes, eg: model, gender or term
+    pub fn with_portfolio_columns(mut self, portfolio_columns: &[&str]) -> Self {
+        self.portfolio_columns = portfolio_columns.iter().map(|c| c.to_string()).collect();
+        self
+    }
+
+    // Align every model point to calendar months from the valuation date, with a `date` column
+    pub fn with_valuation_date(mut self, valuation_date: NaiveDate) -> Self {
+        self.valuation_date = Some(valuation_date);
+        self
+    }
+
+    pub fn with_product_registry(mut self, product_registry: ProductRegistry) -> Self {
+        self.product_registry = product_registry;
+        self
+    }
+
     pub fn model_points_count(&self) -> usize {
         // Return the number of model points in the DataFrame
         self.model_points_df.height()
@@ -41,10 +163,13 @@ impl SingleRunSetup {
         // Create the folder if it does not exist
         create_folder(path);
 
-        // Export description & assumption scenario name as JSON
+        // Export description & assumption scenario settings as JSON
         let description_content = serde_json::json!({
             "description": self.description,
-            "assumptions": self.assumption_scenario.name
+            "assumptions": self.assumption_scenario.to_json(),
+            "output_profile": self.output_profile.to_json(),
+            "portfolio_columns": self.portfolio_columns,
+            "valuation_date": self.valuation_date.map(|d| d.to_string())
         })
         .to_string();
 
@@ -83,21 +208,54 @@ impl SingleRunSetup {
             .unwrap_or_default()
             .to_string();
 
-        let assumptions_name = info_json["assumptions"]
+        // Results exported before the scenario settings were saved hold the scenario name only
+        let assumption_scenario = match &info_json["assumptions"] {
+            serde_json::Value::String(name) => AssumptionScenario::new_by_name(name)?,
+            assumptions => AssumptionScenario::from_json(assumptions)?,
+        };
+
+        let valuation_date = info_json["valuation_date"]
             .as_str()
-            .unwrap_or_default()
-            .to_string();
+            .map(|d| d.parse::<NaiveDate>())
+            .transpose()
+            .map_err(|e| {
+                PolarsError::ComputeError(format!("Invalid valuation date: {e}").into())
+            })?;
+
+        // Results exported before these were saved ran the full profile without a portfolio split
+        let output_profile = match &info_json["output_profile"] {
+            serde_json::Value::Null => OutputProfile::default(),
+            profile => OutputProfile::from_json(profile)?,
+        };
+
+        let portfolio_columns: Vec<String> = match &info_json["portfolio_columns"] {
+            serde_json::Value::Null => Vec::new(),
+            columns => columns
+                .as_array()
+                .and_then(|columns| {
+                    columns
+                        .iter()
+                        .map(|c| c.as_str().map(String::from))
+                        .collect()
+                })
+                .ok_or_else(|| {
+                    PolarsError::ComputeError(
+                        format!("Invalid portfolio columns: {columns}").into(),
+                    )
+                })?,
+        };
 
         // Import model points DataFrame
         let model_points_path = path.join("model_points.parquet");
         let mut model_points_file = File::open(model_points_path)?;
         let model_points_df = ParquetReader::new(&mut model_points_file).finish()?;
 
-        // Create the RunSetup instance
+        // Create the RunSetup instance - products outside the default registry must be re-attached
         let result = SingleRunSetup {
-            description,
-            model_points_df,
-            assumption_scenario: AssumptionScenario::new_by_name(&assumptions_name)?,
+            output_profile,
+            portfolio_columns,
+            valuation_date,
+            ..SingleRunSetup::new(&description, model_points_df, assumption_scenario)
         };
 
         Ok(result)
@@ -111,6 +269,9 @@ impl SingleRunSetup {
 pub struct SingleRunResult {
     pub setup: SingleRunSetup,
     pub projected_df: DataFrame, // This is expensive procedure, so we store the result
+    pub pv_df: DataFrame,        // Present values - one row per model point
+    pub pv_total_df: DataFrame,  // Present values - run total
+    pub portfolio_df: DataFrame, // Cashflows and decrements summed over the model points - one row per t
 }
 
 #[allow(dead_code)]
@@ -126,11 +287,51 @@ impl SingleRunResult {
         let setup_path = path.join("run_setup");
         self.setup.export(setup_path.to_str().unwrap())?;
 
-        // Export projected DataFrame
-        let projected_df_path = path.join("projected_df.parquet");
-        let mut projected_df_file = File::create(projected_df_path)?;
-        let mut projected_df = self.projected_df.clone();
-        ParquetWriter::new(&mut projected_df_file).finish(&mut projected_df)?;
+        // Export projected DataFrame - none under the pv_only output profile
+        if self.projected_df.width() > 0 {
+            let projected_df_path = path.join("projected_df.parquet");
+            let mut projected_df_file = File::create(projected_df_path)?;
+            let mut projected_df = self.projected_df.clone();
+            ParquetWriter::new(&mut projected_df_file).finish(&mut projected_df)?;
+        }
+
+        // Export present value summaries
+        for (file_name, df) in [
+            ("pv_df.parquet", &self.pv_df),
+            ("pv_total_df.parquet", &self.pv_total_df),
+            ("portfolio_df.parquet", &self.portfolio_df),
+        ] {
+            let mut file = File::create(path.join(file_name))?;
+            ParquetWriter::new(&mut file).finish(&mut df.clone())?;
+        }
+
+        Ok(())
+    }
+
+    // Annual figures by policy or calendar year and any model point attributes
+    pub fn annual_df(&self, basis: YearBasis, group_columns: &[&str]) -> PolarsResult<DataFrame> {
+        annual_df(
+            &self.projected_df,
+            &self.setup.model_points_df,
+            basis,
+            group_columns,
+        )
+    }
+
+    // Export the annual figures next to projected_df.parquet, eg: policy_year_df.parquet
+    pub fn export_annual_df(
+        &self,
+        folder_path_str: &str,
+        basis: YearBasis,
+        group_columns: &[&str],
+    ) -> PolarsResult<()> {
+        let path = Path::new(&folder_path_str);
+        create_folder(path);
+
+        let file_path = path.join(format!("{}_df.parquet", basis.column_name()));
+        let mut file = File::create(file_path)?;
+        let mut df = self.annual_df(basis, group_columns)?;
+        ParquetWriter::new(&mut file).finish(&mut df)?;
 
         Ok(())
     }
@@ -151,15 +352,49 @@ impl SingleRunResult {
 
         // Import projected DataFrame
         let projected_df_path = path.join("projected_df.parquet");
-        let projected_df = LazyFrame::scan_parquet(
-            projected_df_path.to_str().unwrap(),
-            ScanArgsParquet::default(),
-        )?
-        .collect()?;
+        let projected_df = if projected_df_path.exists() {
+            LazyFrame::scan_parquet(
+                projected_df_path.to_str().unwrap(),
+                ScanArgsParquet::default(),
+            )?
+            .collect()?
+        } else {
+            DataFrame::empty()
+        };
+
+        // Import present value summaries - recalculate them for results exported without
+        let pv_df_path = path.join("pv_df.parquet");
+        let pv_total_df_path = path.join("pv_total_df.parquet");
+        let (pv_df, pv_total_df) = if pv_df_path.exists() && pv_total_df_path.exists() {
+            (
+                ParquetReader::new(&mut File::open(pv_df_path)?).finish()?,
+                ParquetReader::new(&mut File::open(pv_total_df_path)?).finish()?,
+            )
+        } else {
+            _pv_summary(&projected_df)?
+        };
+
+        // Import portfolio totals - recalculate them for results exported without
+        let portfolio_df_path = path.join("portfolio_df.parquet");
+        let portfolio_df = if portfolio_df_path.exists() {
+            ParquetReader::new(&mut File::open(portfolio_df_path)?).finish()?
+        } else if projected_df.width() > 0 {
+            let block_df = portfolio_df(
+                &projected_df,
+                &setup.model_points_df,
+                &setup.portfolio_columns,
+            )?;
+            combine_portfolio_dfs(vec![block_df], &setup.portfolio_columns)?
+        } else {
+            DataFrame::empty()
+        };
 
         let result = SingleRunResult {
             setup,
             projected_df,
+            pv_df,
+            pv_total_df,
+            portfolio_df,
         };
 
         Ok(result)
@@ -169,68 +404,389 @@ impl SingleRunResult {
 //---------------------------------------------------------------------------------------------------------
 // PRIVATE
 //---------------------------------------------------------------------------------------------------------
-// Process data in chunks to avoid stack overflow
-const CHUNK_SIZE: usize = 100;
+// Each block of model points is projected at once by the product's vectorised engine
+const BLOCK_SIZE: usize = 1000;
 
 fn _project_single_run(setup: &SingleRunSetup) -> PolarsResult<SingleRunResult> {
-    // Convert model points DataFrame to vector
-    let model_points_vec = __convert_model_points_df_to_vector(&setup.model_points_df)?;
-
-    // Process chunks of model points in parallel with limited threads
-    let chunks = model_points_vec
-        .chunks(CHUNK_SIZE)
-        .collect::<Vec<&[ModelPoint]>>();
-
-    let all_chunk_lfs = chunks
+    // Split model points DataFrame into blocks of a single product
+    let blocks = setup
+        .product_registry
+        .blocks_from_df(&setup.model_points_df, BLOCK_SIZE)?;
+
+    // Process blocks in parallel - the engine only flattens the output columns and those present values
+    // and portfolio totals need, which are then dropped before the blocks are stacked
+    let options = ProjectionOptions {
+        columns: setup
+            .output_profile
+            .projection_columns(&setup.portfolio_columns),
+        valuation_date: setup.valuation_date,
+    };
+    let all_blocks = blocks
         .into_par_iter()
-        .map(|chunk| {
-            // Process each chunk sequentially (no nested parallelism)
-            let all_lfs = chunk
-                .iter()
-                .map(|mp| mp.project(&setup.assumption_scenario))
-                .collect::<PolarsResult<Vec<LazyFrame>>>()?;
+        .map(|(block_df, projector)| {
+            let projected_df = projector(&block_df, &setup.assumption_scenario, &options)?;
+            let pv_df = _pv_by_id(&projected_df)?;
+            let block_portfolio_df =
+                portfolio_df(&projected_df, &block_df, &setup.portfolio_columns)?;
+            let output_df = _select_output(projected_df, &setup.output_profile)?;
+            Ok((output_df, pv_df, block_portfolio_df))
+        })
+        .collect::<PolarsResult<Vec<(DataFrame, DataFrame, DataFrame)>>>()?;
 
-            // Concatenate LazyFrames within the chunk and collect to DataFrame
-            let lf = concat(all_lfs, Default::default())?;
+    // Products may not output every present value item, so block present values are stacked diagonally
+    let pv_df = _stack_diagonal(all_blocks.iter().map(|(_, df, _)| df.clone()).collect())?;
+    let pv_total_df = _pv_total(&pv_df)?;
 
-            Ok(lf)
-        })
-        .collect::<PolarsResult<Vec<LazyFrame>>>()?;
+    let block_portfolio_dfs = all_blocks.iter().map(|(_, _, df)| df.clone()).collect();
+    let portfolio_df = combine_portfolio_dfs(block_portfolio_dfs, &setup.portfolio_columns)?;
 
-    // Concatenate all chunk DataFrames
-    let final_lf = concat(all_chunk_lfs, Default::default())?;
-    let final_df = final_lf.collect()?;
+    // Stack all block DataFrames - products may output extra columns (eg: date), missing ones are null
+    let final_df = _stack_diagonal(
+        all_blocks
+            .into_iter()
+            .filter(|(block_df, _, _)| block_df.width() > 0)
+            .map(|(block_df, _, _)| block_df)
+            .collect(),
+    )?;
 
     // Return the result with run setup and projected DataFrame
     let result = SingleRunResult {
         setup: setup.clone(),
         projected_df: final_df,
+        pv_df,
+        pv_total_df,
+        portfolio_df,
     };
 
     Ok(result)
 }
 
-fn __convert_model_points_df_to_vector(df: &DataFrame) -> PolarsResult<Vec<ModelPoint>> {
-    todo!("Implement conversion from DataFrame to Vec<ModelPoint>");
-    // let id = df.column("id")?.i32()?;
-    // let entry_age = df.column("entry_age")?.i32()?;
-    // let gender = df.column("gender")?.str()?;
-    // let term = df.column("term")?.i32()?;
-    // let policy_count = df.column("policy_count")?.f64()?;
-    // let sum_insured = df.column("sum_insured")?.f64()?;
-    // let model = df.column("model")?.str()?;
-
-    // let model_points = (0..df.height())
-    //     .map(|i| ModelPoint {
-    //         id: id.get(i).unwrap(),
-    //         entry_age: entry_age.get(i).unwrap(),
-    //         gender: gender.get(i).unwrap().to_string(),
-    //         term: term.get(i).unwrap(),
-    //         policy_count: policy_count.get(i).unwrap(),
-    //         sum_insured: sum_insured.get(i).unwrap(),
-    //         model: model.get(i).unwrap().to_string(),
-    //     })
-    //     .collect();
-
-    // Ok(model_points)
+// Projected column and the name of its present value - same as result_pv in lifelib
+pub const PV_ITEMS: [(&str, &str); 11] = [
+    ("premiums", "pv_premiums"),
+    ("claims", "pv_claims"),
+    ("expenses", "pv_expenses"),
+    ("commissions", "pv_commissions"),
+    ("ceded_claims", "pv_ceded_claims"),
+    ("reinsurance_premiums", "pv_reinsurance_premiums"),
+    ("reinsurance_commissions", "pv_reinsurance_commissions"),
+    ("premium_tax", "pv_premium_tax"),
+    ("tax", "pv_tax"),
+    ("net_cf", "pv_net_cf"),
+    ("pols_if", "pv_pols_if"),
+];
+
+// Columns kept by the cashflows output profile, when the product outputs them
+const CASHFLOW_COLUMNS: [&str; 21] = [
+    "id",
+    "t",
+    "date",
+    "duration",
+    "pols_if",
+    "pols_maturity",
+    "pols_death",
+    "pols_lapse",
+    "premiums",
+    "claims",
+    "expenses",
+    "commissions",
+    "ceded_claims",
+    "reinsurance_premiums",
+    "reinsurance_commissions",
+    "premium_tax",
+    "tax",
+    "net_cf",
+    "investment_income",
+    "statutory_profit",
+    "gross_premium_reserve",
+];
+
+fn _select_output(
+    projected_df: DataFrame,
+    output_profile: &OutputProfile,
+) -> PolarsResult<DataFrame> {
+    let available: Vec<String> = projected_df
+        .get_column_names()
+        .iter()
+        .map(|c| c.to_string())
+        .collect();
+
+    match output_profile.columns(&available) {
+        None => Ok(projected_df),
+        Some(columns) if columns.is_empty() => Ok(DataFrame::empty()),
+        Some(columns) => projected_df.select(columns),
+    }
+}
+
+// Present value at t = 0 of each item, by model point and in total
+fn _pv_summary(projected_df: &DataFrame) -> PolarsResult<(DataFrame, DataFrame)> {
+    let pv_df = _pv_by_id(projected_df)?;
+    let pv_total_df = _pv_total(&pv_df)?;
+
+    Ok((pv_df, pv_total_df))
+}
+
+fn _stack_diagonal(dfs: Vec<DataFrame>) -> PolarsResult<DataFrame> {
+    if dfs.is_empty() {
+        return Ok(DataFrame::empty());
+    }
+
+    let args = UnionArgs {
+        diagonal: true,
+        ..Default::default()
+    };
+    concat(
+        dfs.into_iter().map(|df| df.lazy()).collect::<Vec<_>>(),
+        args,
+    )?
+    .collect()
+}
+
+// Only the items the product projects - none without a discount factor
+fn _pv_by_id(projected_df: &DataFrame) -> PolarsResult<DataFrame> {
+    let has = |name: &str| projected_df.column(name).is_ok();
+    let pv_exprs = PV_ITEMS
+        .iter()
+        .filter(|(name, _)| has("discount_factor") && has(name))
+        .map(|(name, pv_name)| (col(*name) * col("discount_factor")).sum().alias(*pv_name))
+        .collect::<Vec<Expr>>();
+
+    projected_df
+        .clone()
+        .lazy()
+        .group_by_stable([col("id")])
+        .agg(pv_exprs)
+        .collect()
+}
+
+fn _pv_total(pv_df: &DataFrame) -> PolarsResult<DataFrame> {
+    pv_df
+        .clone()
+        .lazy()
+        .select(
+            PV_ITEMS
+                .iter()
+                .filter(|(_, pv_name)| pv_df.column(pv_name).is_ok())
+                .map(|(_, pv_name)| col(*pv_name).sum())
+                .collect::<Vec<Expr>>(),
+        )
+        .collect()
+}
+
+//---------------------------------------------------------------------------------------------------------
+// UNIT TESTS
+//---------------------------------------------------------------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assumptions::premium_rules::PremiumRules;
+    use crate::assumptions::tax::TaxBasis;
+    use crate::mp_gen::pricing_mp_gen::{PricingGrid, test_pricing_grid};
+
+    #[test]
+    fn test_method_single_run_setup_projection_run_pv_summary() {
+        let model_points_df = test_pricing_grid(&[])
+            .with_age_range(30, 34)
+            .generate()
+            .unwrap();
+        let setup = SingleRunSetup::new(
+            "PV summary",
+            model_points_df.clone(),
+            AssumptionScenario::new_by_name("pricing").unwrap(),
+        );
+
+        let result = setup.projection_run().unwrap();
+
+        println!("{:?}", result.pv_df);
+        println!("{:?}", result.pv_total_df);
+
+        assert_eq!(result.pv_df.height(), model_points_df.height());
+        assert_eq!(result.pv_total_df.height(), 1);
+
+        // Run total is the sum over model points, and net cashflow is consistent with its items
+        let pv =
+            |df: &DataFrame, name: &str| df.column(name).unwrap().f64().unwrap().sum().unwrap();
+        for (_, pv_name) in PV_ITEMS.iter() {
+            assert!((pv(&result.pv_df, pv_name) - pv(&result.pv_total_df, pv_name)).abs() < 1e-6);
+        }
+        let pv_net_cf = pv(&result.pv_total_df, "pv_premiums")
+            - pv(&result.pv_total_df, "pv_claims")
+            - pv(&result.pv_total_df, "pv_expenses")
+            - pv(&result.pv_total_df, "pv_commissions");
+        assert!((pv(&result.pv_total_df, "pv_net_cf") - pv_net_cf).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_method_single_run_setup_output_profile() {
+        let model_points_df = PricingGrid::new("s_model")
+            .with_age_range(30, 34)
+            .with_terms(&[10])
+            .with_genders(&["M", "F"])
+            .with_sum_insureds(&[100_000.0])
+            .generate()
+            .unwrap();
+        let setup = SingleRunSetup::new(
+            "Output profile",
+            model_points_df,
+            AssumptionScenario::new_by_name("pricing").unwrap(),
+        );
+
+        let full = setup.projection_run().unwrap();
+        let cashflows = setup
+            .clone()
+            .with_output_profile(OutputProfile::Cashflows)
+            .projection_run()
+            .unwrap();
+        let pv_only = setup
+            .clone()
+            .with_output_profile(OutputProfile::PvOnly)
+            .projection_run()
+            .unwrap();
+        let custom = setup
+            .clone()
+            .with_output_profile(OutputProfile::Columns(vec![
+                "id".to_string(),
+                "t".to_string(),
+                "net_cf".to_string(),
+            ]))
+            .projection_run()
+            .unwrap();
+
+        println!("{:?}", cashflows.projected_df);
+
+        // Intermediate columns are dropped, the S model has no date
+        let columns = cashflows.projected_df.get_column_names();
+        assert!(!columns.iter().any(|c| c.as_str() == "mort_rate_mth"));
+        assert!(!columns.iter().any(|c| c.as_str() == "date"));
+        assert_eq!(cashflows.projected_df.height(), full.projected_df.height());
+        assert!(
+            cashflows
+                .projected_df
+                .column("net_cf")
+                .unwrap()
+                .equals(full.projected_df.column("net_cf").unwrap())
+        );
+
+        assert_eq!(pv_only.projected_df.width(), 0);
+        assert_eq!(custom.projected_df.width(), 3);
+
+        // Present values do not depend on the output profile
+        for result in [&cashflows, &pv_only, &custom] {
+            assert!(result.pv_df.equals(&full.pv_df));
+            assert!(result.pv_total_df.equals(&full.pv_total_df));
+        }
+
+        // Unknown columns are an error
+        let unknown = setup
+            .with_output_profile(OutputProfile::Columns(vec!["unknown".to_string()]))
+            .projection_run();
+        assert!(unknown.is_err());
+    }
+
+    #[test]
+    fn test_method_single_run_setup_valuation_date() {
+        let model_points_df = PricingGrid::new("s_model")
+            .with_ages(&[30])
+            .with_terms(&[10])
+            .with_genders(&["M", "F"])
+            .with_sum_insureds(&[100_000.0])
+            .generate()
+            .unwrap();
+        let result = SingleRunSetup::new(
+            "Valuation date",
+            model_points_df,
+            AssumptionScenario::new_by_name("pricing").unwrap(),
+        )
+        .with_valuation_date(NaiveDate::from_ymd_opt(2024, 11, 1).unwrap())
+        .projection_run()
+        .unwrap();
+
+        let annual_df = result.annual_df(YearBasis::CalendarYear, &[]).unwrap();
+
+        println!("{annual_df:?}");
+
+        // November 2024 to November 2034
+        let years = annual_df.column("calendar_year").unwrap().i32().unwrap();
+        assert_eq!(years.get(0), Some(2024));
+        assert_eq!(annual_df.height(), 11);
+
+        // Two months in the first calendar year
+        let first_year = annual_df.column("premiums").unwrap().f64().unwrap().get(0);
+        let first_months = result
+            .projected_df
+            .clone()
+            .lazy()
+            .filter(col("t").lt(lit(2.0)))
+            .collect()
+            .unwrap()
+            .column("premiums")
+            .unwrap()
+            .f64()
+            .unwrap()
+            .sum();
+        assert!((first_year.unwrap() - first_months.unwrap()).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_method_single_run_result_export_import() {
+        let model_points_df = test_pricing_grid(&[30, 50]).generate().unwrap();
+        let assumptions = AssumptionScenario::new_by_name("pricing")
+            .unwrap()
+            .with_mass_lapse(0.1)
+            .with_premium_rules(PremiumRules::default().with_policy_fee(120.0))
+            .with_tax(TaxBasis::new().with_corporate_tax_rate(1, 0.2));
+        let result = SingleRunSetup::new("Export", model_points_df, assumptions)
+            .projection_run()
+            .unwrap();
+
+        let folder = std::env::temp_dir().join("act_test_single_run_export");
+        let folder_str = folder.to_str().unwrap();
+        result.export(folder_str).unwrap();
+        let imported = SingleRunResult::import(folder_str).unwrap();
+        std::fs::remove_dir_all(&folder).unwrap();
+
+        println!("{:?}", imported.pv_total_df);
+
+        // The imported setup keeps the scenario settings, so re-running it reproduces the results
+        assert_eq!(
+            imported.setup.assumption_scenario.to_json(),
+            result.setup.assumption_scenario.to_json()
+        );
+        let rerun = imported.setup.projection_run().unwrap();
+        assert!(rerun.pv_total_df.equals(&result.pv_total_df));
+        assert!(imported.pv_total_df.equals(&result.pv_total_df));
+    }
+
+    #[test]
+    fn test_method_single_run_result_export_import_portfolio() {
+        let model_points_df = test_pricing_grid(&[30, 50]).generate().unwrap();
+        let result = SingleRunSetup::new(
+            "Export portfolio",
+            model_points_df,
+            AssumptionScenario::new_by_name("pricing").unwrap(),
+        )
+        .with_output_profile(OutputProfile::Columns(vec![
+            "id".to_string(),
+            "t".to_string(),
+            "net_cf".to_string(),
+        ]))
+        .with_portfolio_columns(&["gender"])
+        .projection_run()
+        .unwrap();
+
+        // Portfolio totals are recalculated on import when not exported
+        let folder = std::env::temp_dir().join("act_test_single_run_export_portfolio");
+        let folder_str = folder.to_str().unwrap();
+        result.export(folder_str).unwrap();
+        std::fs::remove_file(folder.join("portfolio_df.parquet")).unwrap();
+        let imported = SingleRunResult::import(folder_str).unwrap();
+        std::fs::remove_dir_all(&folder).unwrap();
+
+        println!("{:?}", imported.portfolio_df);
+
+        assert_eq!(imported.setup.output_profile, result.setup.output_profile);
+        assert_eq!(imported.setup.portfolio_columns, vec!["gender".to_string()]);
+        let split = |df: &DataFrame| df.select(["gender", "t", "net_cf"]).unwrap();
+        assert!(split(&imported.portfolio_df).equals(&split(&result.portfolio_df)));
+    }
 }